@@ -0,0 +1,46 @@
+/// Replaces every `{key}` of the format with its value
+pub fn fill(format: &str, values: &[(&str, impl AsRef<str>)]) -> String {
+    values
+        .iter()
+        .fold(format.to_string(), |result, (key, value)| {
+            result.replace(&format!("{{{key}}}"), value.as_ref())
+        })
+}
+
+/// Formats bytes in GiB, or MiB under one GiB
+pub fn human_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    const GIB: f64 = MIB * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= GIB {
+        format!("{:.1}GiB", bytes / GIB)
+    } else {
+        format!("{:.0}MiB", bytes / MIB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        let result = fill(
+            "{volume} {description} {unknown}",
+            &[("volume", "50%"), ("description", "Speakers")],
+        );
+
+        assert_eq!(result, "50% Speakers {unknown}");
+    }
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(512 * 1024 * 1024), "512MiB");
+        assert_eq!(
+            human_bytes(3 * 1024 * 1024 * 1024 + 200 * 1024 * 1024),
+            "3.2GiB"
+        );
+        assert_eq!(human_bytes(0), "0MiB");
+    }
+}
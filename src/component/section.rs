@@ -6,8 +6,27 @@ pub struct Component<'a> {
     pub value: String,
 }
 
+impl Component<'_> {
+    /// Component left out of the statusbar
+    pub fn hidden() -> Self {
+        Self {
+            icon: "",
+            name: "",
+            value: String::new(),
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.icon.is_empty() && self.name.is_empty() && self.value.is_empty()
+    }
+}
+
 impl Display for Component<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_hidden() {
+            return Ok(());
+        }
+
         let icon_str = get_final_string(self.icon);
         let name_str = get_final_string(self.name);
 
@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fs, path::Path, process::Command, thread};
 
 use serde::Deserialize;
 
@@ -10,6 +10,7 @@ const ICON_MEDIUM: &str = "󰁿";
 const ICON_LOW: &str = "󰁺";
 const TIME: u64 = 1000;
 const PATH: &str = "/sys/class/power_supply/BAT0/capacity";
+const HYSTERESIS: u8 = 5;
 
 #[derive(Deserialize, Debug)]
 pub struct Battery {
@@ -18,30 +19,41 @@ pub struct Battery {
     pub icon_full: Option<String>,
     pub icon_medium: Option<String>,
     pub icon_low: Option<String>,
+    pub levels: Option<Vec<Level>>,
+    pub critical: Option<u8>,
+    pub critical_command: Option<String>,
     pub path: Option<String>,
+    #[serde(skip)]
+    critical_fired: bool,
+}
+
+/// Icon shown when the battery percentage is at least `min`
+#[derive(Deserialize, Debug)]
+pub struct Level {
+    pub min: u8,
+    pub icon: String,
 }
 
 impl Converter for Battery {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let battery_percentage = fs::read_to_string(self.path.clone().unwrap_or(PATH.to_string()))
-            .map_err(anyhow::Error::msg)?;
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let path = self.path.clone().unwrap_or(PATH.to_string());
+        let battery_percentage = fs::read_to_string(&path).map_err(anyhow::Error::msg)?;
 
         let battery_percentage: u8 = battery_percentage
             .trim()
             .parse()
             .map_err(anyhow::Error::msg)?;
 
+        if self.check_critical(battery_percentage, is_charging(&path))
+            && let Some(command) = &self.critical_command
+        {
+            run_command(command);
+        }
+
         let total = format!("{battery_percentage}%");
 
         let name = self.name.as_deref().unwrap_or(NAME);
-
-        let icon = if battery_percentage > 80 {
-            self.icon_full.as_deref().unwrap_or(ICON_FULL)
-        } else if battery_percentage > 40 {
-            self.icon_medium.as_deref().unwrap_or(ICON_MEDIUM)
-        } else {
-            self.icon_low.as_deref().unwrap_or(ICON_LOW)
-        };
+        let icon = self.icon(battery_percentage);
 
         Ok(Component {
             name,
@@ -55,6 +67,45 @@ impl Converter for Battery {
     }
 }
 
+impl Battery {
+    fn icon(&self, percentage: u8) -> &str {
+        if let Some(levels) = self.levels.as_ref().filter(|l| !l.is_empty()) {
+            return levels
+                .iter()
+                .filter(|l| percentage >= l.min)
+                .max_by_key(|l| l.min)
+                .or_else(|| levels.iter().min_by_key(|l| l.min))
+                .map(|l| l.icon.as_str())
+                .unwrap_or_default();
+        }
+
+        if percentage > 80 {
+            self.icon_full.as_deref().unwrap_or(ICON_FULL)
+        } else if percentage > 40 {
+            self.icon_medium.as_deref().unwrap_or(ICON_MEDIUM)
+        } else {
+            self.icon_low.as_deref().unwrap_or(ICON_LOW)
+        }
+    }
+
+    /// Whether the critical command has to run, once per discharge cycle. It is
+    /// armed again when charging or when the battery goes back above the
+    /// threshold plus a margin
+    fn check_critical(&mut self, percentage: u8, charging: bool) -> bool {
+        let Some(critical) = self.critical else {
+            return false;
+        };
+
+        if charging || percentage > critical.saturating_add(HYSTERESIS) {
+            self.critical_fired = false;
+        } else if percentage <= critical && !self.critical_fired {
+            self.critical_fired = true;
+            return true;
+        }
+        false
+    }
+}
+
 impl Default for Battery {
     fn default() -> Self {
         Self {
@@ -63,8 +114,28 @@ impl Default for Battery {
             icon_full: Some(String::from(ICON_FULL)),
             icon_medium: Some(String::from(ICON_MEDIUM)),
             icon_low: Some(String::from(ICON_LOW)),
+            levels: None,
+            critical: None,
+            critical_command: None,
             path: Some(String::from(PATH)),
+            critical_fired: false,
+        }
+    }
+}
+
+fn is_charging(capacity_path: &str) -> bool {
+    let status_path = Path::new(capacity_path).with_file_name("status");
+    fs::read_to_string(status_path)
+        .map(|status| matches!(status.trim(), "Charging" | "Full"))
+        .unwrap_or(false)
+}
+
+fn run_command(command: &str) {
+    match Command::new("sh").arg("-c").arg(command).spawn() {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
         }
+        Err(e) => eprintln!("Battery critical command error: {e}"),
     }
 }
 
@@ -82,7 +153,7 @@ mod tests {
         let mut file = File::create(&path).unwrap();
         writeln!(file, "90").unwrap();
 
-        let battery = Battery {
+        let mut battery = Battery {
             path: Some(path.to_string_lossy().to_string()),
             ..Default::default()
         };
@@ -100,7 +171,7 @@ mod tests {
         let mut file = File::create(&path).unwrap();
         writeln!(file, "50").unwrap();
 
-        let battery = Battery {
+        let mut battery = Battery {
             path: Some(path.to_string_lossy().to_string()),
             ..Default::default()
         };
@@ -118,7 +189,7 @@ mod tests {
         let mut file = File::create(&path).unwrap();
         writeln!(file, "20").unwrap();
 
-        let battery = Battery {
+        let mut battery = Battery {
             path: Some(path.to_string_lossy().to_string()),
             ..Default::default()
         };
@@ -136,7 +207,7 @@ mod tests {
         let mut file = File::create(&path).unwrap();
         writeln!(file, "invalid").unwrap();
 
-        let battery = Battery {
+        let mut battery = Battery {
             path: Some(path.to_string_lossy().to_string()),
             ..Default::default()
         };
@@ -144,4 +215,49 @@ mod tests {
         let result = battery.convert();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_icon_with_levels() {
+        let battery = Battery {
+            levels: Some(vec![
+                Level {
+                    min: 10,
+                    icon: String::from("low"),
+                },
+                Level {
+                    min: 90,
+                    icon: String::from("full"),
+                },
+                Level {
+                    min: 50,
+                    icon: String::from("half"),
+                },
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(battery.icon(100), "full");
+        assert_eq!(battery.icon(90), "full");
+        assert_eq!(battery.icon(89), "half");
+        assert_eq!(battery.icon(10), "low");
+        assert_eq!(battery.icon(5), "low");
+    }
+
+    #[test]
+    fn test_critical_command_fires_once_per_discharge() {
+        let mut battery = Battery {
+            critical: Some(10),
+            ..Default::default()
+        };
+
+        assert!(battery.check_critical(9, false));
+        assert!(!battery.check_critical(8, false));
+        assert!(!battery.check_critical(12, false));
+        assert!(!battery.check_critical(7, false));
+
+        assert!(!battery.check_critical(7, true));
+        assert!(battery.check_critical(6, false));
+
+        assert!(!Battery::default().check_critical(1, false));
+    }
 }
@@ -1,40 +1,100 @@
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-    thread::sleep,
-    time::Duration,
-};
+use std::{collections::HashMap, fs, path::Path};
 
 use serde::Deserialize;
 
-use crate::{component::section::Component, configuration::device::Converter};
+use crate::{
+    component::{placeholder, section::Component},
+    configuration::device::Converter,
+};
 
 const NAME: &str = "CPU";
 const ICON: &str = " ";
 const TIME: u64 = 1000;
+const FORMAT: &str = "{usage}";
+const STAT: &str = "/proc/stat";
+const LOADAVG: &str = "/proc/loadavg";
+const SYSFS: &str = "/sys/devices/system/cpu";
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
 #[derive(Deserialize, Debug)]
 pub struct Cpu {
     pub time: Option<u64>,
     pub name: Option<String>,
     pub icon: Option<String>,
+    pub format: Option<String>,
+    #[serde(skip)]
+    previous: HashMap<String, CpuTimes>,
+}
+
+/// Idle and total jiffies of a `cpu` line of /proc/stat
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct CpuTimes {
+    idle: u64,
+    total: u64,
 }
 
 impl Converter for Cpu {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let total = match calculate_cpu_usage() {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        // The first tick measures the usage since boot, the next ones since the previous tick
+        let current = fs::read_to_string(STAT)
+            .map(|stat| parse_cpu_times(&stat))
+            .unwrap_or_default();
+
+        let usages = calculate_usages(&self.previous, &current);
+        self.previous = current.into_iter().collect();
+
+        let total = match usages.first().copied().flatten() {
             Some(usage) => format!("{usage:.0}%"),
             None => "-".to_string(),
         };
 
+        let cores = usages.iter().skip(1);
+        let numbers = cores
+            .clone()
+            .map(|usage| usage.map_or("-".to_string(), |u| format!("{u:.0}")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let bars = cores
+            .map(|usage| usage.map_or(' ', bar))
+            .collect::<String>();
+
+        let format = self.format.as_deref().unwrap_or(FORMAT);
+        let mut values = vec![("usage", total), ("cores", numbers), ("bars", bars)];
+
+        if format.contains("{freq") {
+            let frequencies = read_frequencies(Path::new(SYSFS));
+            let average = frequencies.iter().sum::<u64>() / frequencies.len().max(1) as u64;
+            let max = frequencies.iter().max().copied().unwrap_or_default();
+            values.push(("freq", format_frequency(average)));
+            values.push(("freq_max", format_frequency(max)));
+        }
+
+        if format.contains("{governor}") {
+            let governor =
+                fs::read_to_string(Path::new(SYSFS).join("cpu0/cpufreq/scaling_governor"))
+                    .map(|g| g.trim().to_string())
+                    .unwrap_or_else(|_| "-".to_string());
+            values.push(("governor", governor));
+        }
+
+        if format.contains("{load") {
+            let loads = fs::read_to_string(LOADAVG)
+                .ok()
+                .and_then(|loadavg| parse_loadavg(&loadavg))
+                .unwrap_or_else(|| ["-".to_string(), "-".to_string(), "-".to_string()]);
+            values.push(("load", loads.join(" ")));
+            let [load1, load5, load15] = loads;
+            values.push(("load1", load1));
+            values.push(("load5", load5));
+            values.push(("load15", load15));
+        }
+
+        let value = placeholder::fill(format, &values);
+
         let name = self.name.as_deref().unwrap_or(NAME);
         let icon = self.icon.as_deref().unwrap_or(ICON);
 
-        Ok(Component {
-            name,
-            icon,
-            value: total,
-        })
+        Ok(Component { name, icon, value })
     }
 
     fn time(&self) -> u64 {
@@ -48,20 +108,25 @@ impl Default for Cpu {
             time: Some(TIME),
             name: Some(String::from(NAME)),
             icon: Some(String::from(ICON)),
+            format: Some(String::from(FORMAT)),
+            previous: HashMap::new(),
         }
     }
 }
 
-fn read_cpu_times() -> Option<(u64, u64)> {
-    let file = File::open("/proc/stat").ok()?;
-    let mut reader = BufReader::new(file);
-    let mut line = String::new();
-    reader.read_line(&mut line).ok()?;
-    if !line.starts_with("cpu ") {
-        return None;
-    }
+/// Times of the aggregate `cpu` line followed by the ones of every `cpuN` line,
+/// by name
+fn parse_cpu_times(stat: &str) -> Vec<(String, CpuTimes)> {
+    stat.lines()
+        .take_while(|line| line.starts_with("cpu"))
+        .map_while(parse_cpu_line)
+        .collect()
+}
+
+fn parse_cpu_line(line: &str) -> Option<(String, CpuTimes)> {
     let parts: Vec<&str> = line.split_whitespace().collect();
-    // parts[0] = "cpu"
+    // parts[0] = "cpu" or "cpuN"
+    let name = parts.first()?.to_string();
     let times: Vec<u64> = parts[1..]
         .iter()
         .filter_map(|v| v.parse::<u64>().ok())
@@ -74,28 +139,79 @@ fn read_cpu_times() -> Option<(u64, u64)> {
     let idle = times[3]; // idle time is the 4th field
     let total: u64 = times.iter().sum();
 
-    Some((idle, total))
+    Some((name, CpuTimes { idle, total }))
+}
+
+/// Current frequencies in kHz of every core exposing cpufreq
+fn read_frequencies(sysfs: &Path) -> Vec<u64> {
+    fs::read_dir(sysfs)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix("cpu")
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join("cpufreq/scaling_cur_freq")).ok())
+        .filter_map(|freq| freq.trim().parse().ok())
+        .collect()
+}
+
+fn format_frequency(khz: u64) -> String {
+    if khz == 0 {
+        "-".to_string()
+    } else if khz >= 1_000_000 {
+        format!("{:.2}GHz", khz as f64 / 1_000_000.0)
+    } else {
+        format!("{}MHz", khz / 1000)
+    }
 }
 
-fn calculate_cpu_usage() -> Option<f64> {
-    let (idle1, total1) = read_cpu_times()?;
-    sleep(Duration::from_millis(100));
-    let (idle2, total2) = read_cpu_times()?;
+/// The 1, 5 and 15 minutes load averages
+fn parse_loadavg(loadavg: &str) -> Option<[String; 3]> {
+    let mut parts = loadavg.split_whitespace().map(String::from);
+    Some([parts.next()?, parts.next()?, parts.next()?])
+}
 
-    let idle_delta = idle2.saturating_sub(idle1);
-    let total_delta = total2.saturating_sub(total1);
+fn bar(usage: f64) -> char {
+    let index = (usage / 100.0 * BARS.len() as f64) as usize;
+    BARS[index.min(BARS.len() - 1)]
+}
+
+/// Usage of every line since its previous sample. Samples are matched by name,
+/// so a core going offline does not shift the history of the next ones
+fn calculate_usages(
+    previous: &HashMap<String, CpuTimes>,
+    current: &[(String, CpuTimes)],
+) -> Vec<Option<f64>> {
+    current
+        .iter()
+        .map(|(name, times)| {
+            let previous = previous.get(name).copied().unwrap_or_default();
+            calculate_cpu_usage(previous, *times)
+        })
+        .collect()
+}
+
+fn calculate_cpu_usage(previous: CpuTimes, current: CpuTimes) -> Option<f64> {
+    let idle_delta = current.idle.saturating_sub(previous.idle);
+    let total_delta = current.total.saturating_sub(previous.total);
 
     if total_delta == 0 {
         return None;
     }
 
-    let usage = 100.0 * (total_delta - idle_delta) as f64 / total_delta as f64;
+    let usage = 100.0 * total_delta.saturating_sub(idle_delta) as f64 / total_delta as f64;
     Some(usage)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
 
     #[test]
     fn test_cpu_get_time() {
@@ -103,6 +219,8 @@ mod tests {
             time: Some(2000),
             name: None,
             icon: None,
+            format: None,
+            previous: HashMap::new(),
         };
         assert_eq!(cpu.time(), 2000);
 
@@ -112,10 +230,12 @@ mod tests {
 
     #[test]
     fn test_cpu_convert() {
-        let cpu = Cpu {
+        let mut cpu = Cpu {
             time: Some(1000),
             name: Some(String::from("Custom CPU")),
             icon: Some(String::from(ICON)),
+            format: None,
+            previous: HashMap::new(),
         };
 
         let component = cpu.convert().unwrap();
@@ -127,7 +247,7 @@ mod tests {
 
     #[test]
     fn test_cpu_convert_with_default_values() {
-        let cpu = Cpu::default();
+        let mut cpu = Cpu::default();
 
         let component = cpu.convert().unwrap();
 
@@ -135,4 +255,132 @@ mod tests {
         assert_eq!(component.icon, ICON);
         assert!(component.value.ends_with("%"));
     }
+
+    #[test]
+    fn test_parse_cpu_times() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\ncpu1 50 0 25 400 25 0 0 0 0 0\nintr 1 2 3\n";
+
+        assert_eq!(
+            parse_cpu_times(stat),
+            vec![
+                (
+                    String::from("cpu"),
+                    CpuTimes {
+                        idle: 800,
+                        total: 1000
+                    }
+                ),
+                (
+                    String::from("cpu0"),
+                    CpuTimes {
+                        idle: 400,
+                        total: 500
+                    }
+                ),
+                (
+                    String::from("cpu1"),
+                    CpuTimes {
+                        idle: 400,
+                        total: 500
+                    }
+                ),
+            ]
+        );
+        assert!(parse_cpu_times("intr 1 2 3").is_empty());
+    }
+
+    #[test]
+    fn test_read_frequencies() {
+        let dir = tempdir().unwrap();
+        for (cpu, freq) in [("cpu0", "1200000"), ("cpu1", "3600000")] {
+            let cpufreq = dir.path().join(cpu).join("cpufreq");
+            fs::create_dir_all(&cpufreq).unwrap();
+            fs::write(cpufreq.join("scaling_cur_freq"), format!("{freq}\n")).unwrap();
+        }
+        fs::create_dir_all(dir.path().join("cpufreq")).unwrap();
+        fs::create_dir_all(dir.path().join("cpuidle")).unwrap();
+
+        let mut frequencies = read_frequencies(dir.path());
+        frequencies.sort();
+
+        assert_eq!(frequencies, vec![1200000, 3600000]);
+    }
+
+    #[test]
+    fn test_format_frequency() {
+        assert_eq!(format_frequency(2400000), "2.40GHz");
+        assert_eq!(format_frequency(800000), "800MHz");
+        assert_eq!(format_frequency(0), "-");
+    }
+
+    #[test]
+    fn test_parse_loadavg() {
+        assert_eq!(
+            parse_loadavg("0.53 0.26 0.12 2/72 6591\n"),
+            Some(["0.53".to_string(), "0.26".to_string(), "0.12".to_string()])
+        );
+        assert_eq!(parse_loadavg(""), None);
+    }
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(0.0), '▁');
+        assert_eq!(bar(50.0), '▅');
+        assert_eq!(bar(99.0), '█');
+        assert_eq!(bar(100.0), '█');
+    }
+
+    #[test]
+    fn test_cpu_convert_with_cores() {
+        let mut cpu = Cpu {
+            format: Some(String::from("{usage}|{cores}|{bars}")),
+            ..Default::default()
+        };
+        let cores = parse_cpu_times(&fs::read_to_string(STAT).unwrap()).len() - 1;
+
+        let component = cpu.convert().unwrap();
+        let parts: Vec<&str> = component.value.split('|').collect();
+
+        assert!(parts[0].ends_with('%'));
+        assert_eq!(parts[1].split(' ').count(), cores);
+        assert_eq!(parts[2].chars().count(), cores);
+    }
+
+    #[test]
+    fn test_calculate_cpu_usage_between_ticks() {
+        let previous = CpuTimes {
+            idle: 800,
+            total: 1000,
+        };
+        let current = CpuTimes {
+            idle: 950,
+            total: 1200,
+        };
+
+        assert_eq!(calculate_cpu_usage(previous, current), Some(25.0));
+        assert_eq!(calculate_cpu_usage(current, current), None);
+    }
+
+    #[test]
+    fn test_cpu_convert_keeps_previous_sample() {
+        let mut cpu = Cpu::default();
+
+        cpu.convert().unwrap();
+        let first = cpu.previous["cpu"];
+        cpu.convert().unwrap();
+
+        assert!(first.total > 0);
+        assert!(cpu.previous["cpu"].total >= first.total);
+    }
+
+    #[test]
+    fn test_calculate_usages_with_offline_core() {
+        let first = "cpu  100 0 0 300 0\ncpu0 50 0 0 150 0\ncpu1 50 0 0 150 0\ncpu2 0 0 0 0 0\n";
+        let second = "cpu  200 0 0 500 0\ncpu0 150 0 0 250 0\ncpu2 50 0 0 50 0\n";
+        let previous = parse_cpu_times(first).into_iter().collect();
+
+        let usages = calculate_usages(&previous, &parse_cpu_times(second));
+
+        assert_eq!(usages, vec![Some(100.0 / 3.0), Some(50.0), Some(50.0)]);
+    }
 }
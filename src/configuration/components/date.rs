@@ -1,26 +1,62 @@
-use chrono::Local;
+use std::{collections::HashMap, thread, time::Duration};
+
+use chrono::{
+    DateTime, FixedOffset, Local, Utc,
+    format::{Fixed, Item, Numeric, StrftimeItems},
+};
 use serde::Deserialize;
+use tz::TimeZone;
 
-use crate::{component::section::Component, configuration::device::Converter};
+use crate::{
+    component::section::Component,
+    configuration::device::{Converter, Watcher},
+};
 
 const FORMAT: &str = "%A %d/%m/%Y %H:%M";
 const ICON: &str = " ";
 const TIME: u64 = 1000;
+const SECOND: u64 = 1000;
+const MINUTE: u64 = 60 * SECOND;
+const MAX_SLEEP: u64 = SECOND;
 
 #[derive(Deserialize, Debug)]
 pub struct Date {
     pub time: Option<u64>,
     pub format: Option<String>,
     pub icon: Option<String>,
+    /// IANA name from the system zoneinfo, e.g. "America/New_York". Local time when not set
+    pub timezone: Option<String>,
+    /// Clocks shown after the main one
+    pub clocks: Option<Vec<Clock>>,
+    #[serde(skip)]
+    zones: HashMap<String, TimeZone>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Clock {
+    pub label: Option<String>,
+    pub timezone: Option<String>,
+    /// Format of the clock. The format of the date when not set
+    pub format: Option<String>,
 }
 
 impl Converter for Date {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let date_time = Local::now();
-        let formatted = format!(
-            "{}",
-            date_time.format(self.format.as_deref().unwrap_or(FORMAT))
-        );
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let now = Utc::now();
+        let format = self.format.as_deref().unwrap_or(FORMAT);
+
+        let zone = resolve(&mut self.zones, self.timezone.as_deref())?;
+        let mut formatted = format_time(now, zone, format)?;
+
+        for clock in self.clocks.iter().flatten() {
+            let zone = resolve(&mut self.zones, clock.timezone.as_deref())?;
+            let time = format_time(now, zone, clock.format.as_deref().unwrap_or(format))?;
+            match &clock.label {
+                Some(label) => formatted.push_str(&format!(" {label} {time}")),
+                None => formatted.push_str(&format!(" {time}")),
+            }
+        }
+
         let icon = self.icon.as_deref().unwrap_or(ICON);
 
         Ok(Component {
@@ -33,6 +69,55 @@ impl Converter for Date {
     fn time(&self) -> u64 {
         self.time.unwrap_or(TIME)
     }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        self.formats().try_for_each(validate_format)?;
+        self.timezones()
+            .try_for_each(|timezone| load_zone(timezone).map(|_| ()))
+    }
+}
+
+/// Refreshes right after the wall clock crosses the next boundary shown by
+/// the formats, every second or every minute. A longer `time` is rounded up
+/// to a multiple of that boundary. The wall clock jumps on resume or when it
+/// is set, so the boundary is checked again at least every second
+impl Watcher for Date {
+    fn watch(&mut self, notify: &mut dyn FnMut(Component<'_>)) -> anyhow::Result<()> {
+        let granularity = self.granularity();
+        let period = refresh_period(granularity, self.time());
+        loop {
+            let shown = Utc::now().timestamp_millis().div_euclid(period as i64);
+            notify(self.convert()?);
+            while Utc::now().timestamp_millis().div_euclid(period as i64) == shown {
+                thread::sleep(next_sleep(Utc::now(), granularity, self.time()));
+            }
+        }
+    }
+}
+
+impl Date {
+    fn formats(&self) -> impl Iterator<Item = &str> {
+        let format = self.format.as_deref().unwrap_or(FORMAT);
+        let clocks = self.clocks.iter().flatten();
+        std::iter::once(format).chain(clocks.map(move |c| c.format.as_deref().unwrap_or(format)))
+    }
+
+    fn timezones(&self) -> impl Iterator<Item = &str> {
+        let clocks = self.clocks.iter().flatten();
+        self.timezone
+            .as_deref()
+            .into_iter()
+            .chain(clocks.filter_map(|c| c.timezone.as_deref()))
+    }
+
+    /// Miliseconds between changes of the shown time
+    fn granularity(&self) -> u64 {
+        if self.formats().any(shows_seconds) {
+            SECOND
+        } else {
+            MINUTE
+        }
+    }
 }
 
 impl Default for Date {
@@ -41,13 +126,140 @@ impl Default for Date {
             time: Some(TIME),
             format: Some(String::from(FORMAT)),
             icon: Some(String::from(ICON)),
+            timezone: None,
+            clocks: None,
+            zones: HashMap::new(),
         }
     }
 }
 
+/// Time zone read once from the system zoneinfo. None is the local time zone
+fn resolve<'a>(
+    zones: &'a mut HashMap<String, TimeZone>,
+    timezone: Option<&str>,
+) -> anyhow::Result<Option<&'a TimeZone>> {
+    let Some(timezone) = timezone else {
+        return Ok(None);
+    };
+
+    if !zones.contains_key(timezone) {
+        zones.insert(timezone.to_string(), load_zone(timezone)?);
+    }
+
+    Ok(zones.get(timezone))
+}
+
+fn load_zone(timezone: &str) -> anyhow::Result<TimeZone> {
+    TimeZone::from_posix_tz(timezone)
+        .map_err(|e| anyhow::anyhow!("Unknown timezone {timezone}: {e}"))
+}
+
+fn format_time(
+    now: DateTime<Utc>,
+    zone: Option<&TimeZone>,
+    format: &str,
+) -> anyhow::Result<String> {
+    let Some(zone) = zone else {
+        return Ok(now.with_timezone(&Local).format(format).to_string());
+    };
+
+    let local_time_type = zone
+        .find_local_time_type(now.timestamp())
+        .map_err(anyhow::Error::msg)?;
+    let offset = FixedOffset::east_opt(local_time_type.ut_offset())
+        .ok_or_else(|| anyhow::anyhow!("Invalid UTC offset {}", local_time_type.ut_offset()))?;
+    // chrono only knows the offset, so %Z is replaced by the abbreviation of the time zone
+    let format = replace_zone_name(format, local_time_type.time_zone_designation());
+
+    Ok(now.with_timezone(&offset).format(&format).to_string())
+}
+
+/// Fails on the first specifier chrono does not know, such as `%Q`
+fn validate_format(format: &str) -> anyhow::Result<()> {
+    if !StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Ok(());
+    }
+
+    // A specifier is a % followed by optional flags and a letter or a %
+    let specifier = format
+        .match_indices('%')
+        .map(|(start, _)| {
+            let rest = &format[start + 1..];
+            let end = rest
+                .find(|c: char| c.is_ascii_alphabetic() || c == '%')
+                .map_or(rest.len(), |i| i + 1);
+            &format[start..start + 1 + end]
+        })
+        .find(|specifier| StrftimeItems::new(specifier).any(|item| item == Item::Error));
+
+    match specifier {
+        Some(specifier) => {
+            anyhow::bail!("Invalid date format \"{format}\": unknown specifier {specifier}")
+        }
+        None => anyhow::bail!("Invalid date format \"{format}\""),
+    }
+}
+
+fn shows_seconds(format: &str) -> bool {
+    StrftimeItems::new(format).any(|item| {
+        matches!(
+            item,
+            Item::Numeric(
+                Numeric::Second | Numeric::Nanosecond | Numeric::Timestamp,
+                _
+            ) | Item::Fixed(
+                Fixed::Nanosecond
+                    | Fixed::Nanosecond3
+                    | Fixed::Nanosecond6
+                    | Fixed::Nanosecond9
+                    | Fixed::RFC2822
+                    | Fixed::RFC3339
+            )
+        )
+    })
+}
+
+/// Miliseconds between refreshes, `time` rounded up to a multiple of the granularity
+fn refresh_period(granularity: u64, time: u64) -> u64 {
+    time.max(granularity).div_ceil(granularity) * granularity
+}
+
+/// Time until the next multiple of the refresh period
+fn next_refresh(now: DateTime<Utc>, granularity: u64, time: u64) -> Duration {
+    let period = refresh_period(granularity, time);
+    let elapsed = now.timestamp_millis().rem_euclid(period as i64) as u64;
+    Duration::from_millis(period - elapsed)
+}
+
+/// Time until the next refresh, capped so a jump of the wall clock is noticed
+fn next_sleep(now: DateTime<Utc>, granularity: u64, time: u64) -> Duration {
+    next_refresh(now, granularity, time).min(Duration::from_millis(MAX_SLEEP))
+}
+
+fn replace_zone_name(format: &str, name: &str) -> String {
+    let mut replaced = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            replaced.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Z') => replaced.push_str(name),
+            Some(next) => {
+                replaced.push(c);
+                replaced.push(next);
+            }
+            None => replaced.push(c),
+        }
+    }
+    replaced
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone as _;
 
     #[test]
     fn test_date_get_time() {
@@ -55,6 +267,7 @@ mod tests {
             time: Some(2000),
             format: None,
             icon: None,
+            ..Default::default()
         };
         assert_eq!(date.time(), 2000);
 
@@ -64,10 +277,11 @@ mod tests {
 
     #[test]
     fn test_date_convert() {
-        let date = Date {
+        let mut date = Date {
             time: Some(1000),
             format: Some(String::from("%Y-%m-%d")),
             icon: Some(String::from(ICON)),
+            ..Default::default()
         };
 
         let component = date.convert().unwrap();
@@ -78,7 +292,7 @@ mod tests {
 
     #[test]
     fn test_date_convert_with_default_values() {
-        let date = Date::default();
+        let mut date = Date::default();
 
         let component = date.convert().unwrap();
 
@@ -86,16 +300,186 @@ mod tests {
     }
 
     #[test]
-    fn test_date_convert_with_invalid_format() {
+    fn test_date_validate_with_invalid_format() {
         let date = Date {
             time: Some(1000),
-            format: Some(String::from("invalid_format")),
+            format: Some(String::from("%d/%m %Q")),
             icon: Some(String::from(ICON)),
+            ..Default::default()
+        };
+
+        let error = date.validate().unwrap_err().to_string();
+
+        assert!(error.contains("unknown specifier %Q"));
+    }
+
+    #[test]
+    fn test_format_time_in_timezone() {
+        let mut zones = HashMap::new();
+        let new_york = resolve(&mut zones, Some("America/New_York"))
+            .unwrap()
+            .unwrap()
+            .clone();
+        let winter = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2024, 7, 15, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            format_time(winter, Some(&new_york), "%H:%M %Z").unwrap(),
+            "07:00 EST"
+        );
+        assert_eq!(
+            format_time(summer, Some(&new_york), "%H:%M %Z %%Z").unwrap(),
+            "08:00 EDT %Z"
+        );
+    }
+
+    #[test]
+    fn test_date_convert_with_clocks() {
+        let mut date = Date {
+            format: Some(String::from("%H:%M")),
+            timezone: Some(String::from("UTC")),
+            clocks: Some(vec![
+                Clock {
+                    label: Some(String::from("NYC")),
+                    timezone: Some(String::from("America/New_York")),
+                    format: None,
+                },
+                Clock {
+                    label: None,
+                    timezone: Some(String::from("Asia/Tokyo")),
+                    format: Some(String::from("%H:%M %Z")),
+                },
+            ]),
+            ..Default::default()
         };
 
         let component = date.convert().unwrap();
+        let parts: Vec<&str> = component.value.split(' ').collect();
 
-        assert_eq!(component.icon, ICON);
-        assert!(!component.value.is_empty());
+        assert_eq!(parts.len(), 5);
+        assert_eq!(parts[1], "NYC");
+        assert_eq!(parts[4], "JST");
+    }
+
+    #[test]
+    fn test_validate_format() {
+        assert!(validate_format(FORMAT).is_ok());
+        assert!(validate_format("%-d %.3f %:z %% literal").is_ok());
+        assert!(validate_format("invalid_format").is_ok());
+
+        let error = validate_format("%Y %Q").unwrap_err().to_string();
+        assert!(error.contains("unknown specifier %Q"));
+        assert!(validate_format("%Y %").is_err());
+    }
+
+    #[test]
+    fn test_date_validate_clock_formats() {
+        let date = Date {
+            clocks: Some(vec![Clock {
+                label: Some(String::from("UTC")),
+                timezone: Some(String::from("UTC")),
+                format: Some(String::from("%H:%M %J")),
+            }]),
+            ..Default::default()
+        };
+
+        assert!(date.validate().is_err());
+        assert!(Date::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_granularity() {
+        let minutes = Date::default();
+        assert_eq!(minutes.granularity(), MINUTE);
+
+        let seconds = Date {
+            format: Some(String::from("%T")),
+            ..Default::default()
+        };
+        assert_eq!(seconds.granularity(), SECOND);
+
+        let clock_seconds = Date {
+            format: Some(String::from("%H:%M")),
+            clocks: Some(vec![Clock {
+                label: None,
+                timezone: None,
+                format: Some(String::from("%H:%M:%S")),
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(clock_seconds.granularity(), SECOND);
+    }
+
+    #[test]
+    fn test_next_refresh() {
+        let now = Utc
+            .with_ymd_and_hms(2024, 1, 15, 12, 30, 42)
+            .unwrap()
+            .checked_add_signed(chrono::Duration::milliseconds(250))
+            .unwrap();
+
+        assert_eq!(next_refresh(now, SECOND, 1000), Duration::from_millis(750));
+        assert_eq!(
+            next_refresh(now, MINUTE, 1000),
+            Duration::from_millis(17750)
+        );
+        assert_eq!(
+            next_refresh(now, MINUTE, 60000),
+            Duration::from_millis(17750)
+        );
+        assert_eq!(
+            next_refresh(now, MINUTE, 90000),
+            Duration::from_millis(77750)
+        );
+    }
+
+    #[test]
+    fn test_next_sleep_is_capped() {
+        let now = Utc
+            .with_ymd_and_hms(2024, 1, 15, 12, 30, 59)
+            .unwrap()
+            .checked_add_signed(chrono::Duration::milliseconds(250))
+            .unwrap();
+
+        assert_eq!(next_sleep(now, SECOND, 1000), Duration::from_millis(750));
+        assert_eq!(next_sleep(now, MINUTE, 1000), Duration::from_millis(750));
+
+        let earlier = now - chrono::Duration::seconds(30);
+        assert_eq!(
+            next_sleep(earlier, MINUTE, 60000),
+            Duration::from_millis(MAX_SLEEP)
+        );
+        assert_eq!(
+            next_sleep(earlier, MINUTE, 90000),
+            Duration::from_millis(MAX_SLEEP)
+        );
+    }
+
+    #[test]
+    fn test_date_validate_with_unknown_timezone() {
+        let date = Date {
+            timezone: Some(String::from("Mars/Olympus_Mons")),
+            ..Default::default()
+        };
+        let clock = Date {
+            clocks: Some(vec![
+                Clock {
+                    label: Some(String::from("UTC")),
+                    timezone: Some(String::from("UTC")),
+                    format: None,
+                },
+                Clock {
+                    label: Some(String::from("MARS")),
+                    timezone: Some(String::from("Mars/Olympus_Mons")),
+                    format: None,
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let error = date.validate().unwrap_err().to_string();
+
+        assert!(error.contains("Unknown timezone Mars/Olympus_Mons"));
+        assert!(clock.validate().is_err());
     }
 }
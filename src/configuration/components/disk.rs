@@ -1,13 +1,21 @@
 use libc::{c_char, statvfs};
 use serde::Deserialize;
-use std::ffi::CString;
+use std::{ffi::CString, fs};
 
-use crate::{component::section::Component, configuration::device::Converter};
+use crate::{
+    component::{
+        placeholder::{self, human_bytes},
+        section::Component,
+    },
+    configuration::device::Converter,
+};
 
 const NAME: &str = "DISK";
 const ICON: &str = "󰋊 ";
 const UNIT: &str = "/";
 const TIME: u64 = 2000;
+const FORMAT: &str = "{percentage}";
+const MOUNTINFO: &str = "/proc/self/mountinfo";
 
 #[derive(Deserialize, Debug)]
 pub struct Disk {
@@ -15,24 +23,53 @@ pub struct Disk {
     pub name: Option<String>,
     pub icon: Option<String>,
     pub unit: Option<String>,
+    pub units: Option<Vec<String>>,
+    pub format: Option<String>,
+}
+
+/// Sizes in bytes of a mounted filesystem
+#[derive(Debug, PartialEq)]
+struct Usage {
+    total: u64,
+    available: u64,
+    inodes: u64,
+    free_inodes: u64,
 }
 
 impl Converter for Disk {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let c_path =
-            CString::new(self.unit.as_deref().unwrap_or(UNIT)).map_err(anyhow::Error::msg)?;
-
-        let mut stat: statvfs = unsafe { std::mem::zeroed() };
-        let ret = unsafe { statvfs(c_path.as_ptr() as *const c_char, &mut stat) };
-
-        let total = if ret != 0 {
-            anyhow::bail!("Invalid Unit");
-        } else {
-            let total = stat.f_blocks * stat.f_frsize;
-            let available = stat.f_bavail * stat.f_frsize;
-            let used = total.saturating_sub(available);
-            format!("{:.0}%", used * 100 / total)
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let units = match &self.units {
+            Some(units) if !units.is_empty() => units.clone(),
+            _ => vec![self.unit.clone().unwrap_or(UNIT.to_string())],
         };
+        let format = self.format.as_deref().unwrap_or(FORMAT);
+
+        // Missing paths, automount points with nothing mounted and pseudo
+        // filesystems without blocks are left out
+        let mountinfo = fs::read_to_string(MOUNTINFO).unwrap_or_default();
+        let values: Vec<String> = units
+            .iter()
+            .filter_map(|unit| usage(unit, &mountinfo).map(|usage| (unit, usage)))
+            .map(|(unit, usage)| {
+                let used = usage.total.saturating_sub(usage.available);
+                let used_inodes = usage.inodes.saturating_sub(usage.free_inodes);
+                placeholder::fill(
+                    format,
+                    &[
+                        ("mount", unit.to_string()),
+                        ("percentage", percentage(used, usage.total)),
+                        ("used", human_bytes(used)),
+                        ("free", human_bytes(usage.available)),
+                        ("total", human_bytes(usage.total)),
+                        ("inodes", percentage(used_inodes, usage.inodes)),
+                    ],
+                )
+            })
+            .collect();
+
+        if values.is_empty() {
+            return Ok(Component::hidden());
+        }
 
         let name = self.name.as_deref().unwrap_or(NAME);
         let icon = self.icon.as_deref().unwrap_or(ICON);
@@ -40,7 +77,7 @@ impl Converter for Disk {
         Ok(Component {
             name,
             icon,
-            value: total,
+            value: values.join(" "),
         })
     }
 
@@ -56,10 +93,84 @@ impl Default for Disk {
             name: Some(String::from(NAME)),
             icon: Some(String::from(ICON)),
             unit: Some(String::from(UNIT)),
+            units: None,
+            format: Some(String::from(FORMAT)),
         }
     }
 }
 
+fn usage(unit: &str, mountinfo: &str) -> Option<Usage> {
+    let path = fs::canonicalize(unit).ok()?;
+    if mount_type(mountinfo, &path.to_string_lossy()) == Some("autofs") {
+        return None;
+    }
+
+    let c_path = CString::new(unit).ok()?;
+
+    let mut stat: statvfs = unsafe { std::mem::zeroed() };
+    let ret = unsafe { statvfs(c_path.as_ptr() as *const c_char, &mut stat) };
+
+    if ret != 0 || stat.f_blocks == 0 {
+        return None;
+    }
+
+    Some(Usage {
+        total: stat.f_blocks * stat.f_frsize,
+        available: stat.f_bavail * stat.f_frsize,
+        inodes: stat.f_files,
+        free_inodes: stat.f_ffree,
+    })
+}
+
+/// Filesystem type of the topmost mount at the path in /proc/self/mountinfo.
+/// An automount point with nothing mounted on it is only an `autofs` mount,
+/// and a directory that is not a mount point has no entry
+fn mount_type<'a>(mountinfo: &'a str, path: &str) -> Option<&'a str> {
+    // Later entries are mounted on top of the earlier ones at the same path
+    mountinfo
+        .lines()
+        .rev()
+        .filter_map(|line| {
+            // Fields before the separator are id, parent, major:minor, root,
+            // mount point and options. The filesystem type follows it
+            let (mount, filesystem) = line.split_once(" - ")?;
+            let mount_point = mount.split(' ').nth(4)?;
+            Some((unescape(mount_point), filesystem.split(' ').next()?))
+        })
+        .filter(|(mount_point, _)| mount_point == path)
+        .map(|(_, filesystem)| filesystem)
+        .next()
+}
+
+/// Mount points escape space, tab, newline and backslash as octal, e.g. `\040`
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(start) = rest.find('\\') {
+        unescaped.push_str(&rest[..start]);
+        let code = rest.get(start + 1..start + 4);
+        match code.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[start + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn percentage(used: u64, total: u64) -> String {
+    match (used * 100).checked_div(total) {
+        Some(percentage) => format!("{percentage}%"),
+        None => "-".to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +182,8 @@ mod tests {
             name: None,
             icon: None,
             unit: None,
+            units: None,
+            format: None,
         };
         assert_eq!(disk.time(), 3000);
 
@@ -80,11 +193,13 @@ mod tests {
 
     #[test]
     fn test_disk_convert() {
-        let disk = Disk {
+        let mut disk = Disk {
             time: Some(2000),
             name: Some(String::from("Custom Disk")),
             icon: Some(String::from(ICON)),
             unit: Some(String::from("/")),
+            units: None,
+            format: None,
         };
 
         let component = disk.convert().unwrap();
@@ -96,7 +211,7 @@ mod tests {
 
     #[test]
     fn test_disk_convert_with_default_values() {
-        let disk = Disk::default();
+        let mut disk = Disk::default();
 
         let component = disk.convert().unwrap();
 
@@ -107,15 +222,87 @@ mod tests {
 
     #[test]
     fn test_disk_convert_with_invalid_unit() {
-        let disk = Disk {
+        let mut disk = Disk {
             time: Some(2000),
             name: Some(String::from("Invalid Disk")),
             icon: Some(String::from(ICON)),
             unit: Some(String::from("invalid_unit")),
+            units: None,
+            format: None,
         };
 
-        let result = disk.convert();
+        let component = disk.convert().unwrap();
+
+        assert!(component.is_hidden());
+    }
+
+    #[test]
+    fn test_disk_convert_with_multiple_units() {
+        let mut disk = Disk {
+            units: Some(vec![
+                String::from("/"),
+                String::from("/nonexistent/mount"),
+                String::from("/proc"),
+            ]),
+            format: Some(String::from("{mount} {used}/{total} {free} {inodes}")),
+            ..Default::default()
+        };
+
+        let component = disk.convert().unwrap();
+
+        assert!(component.value.starts_with("/ "));
+        assert!(component.value.contains("iB/"));
+        assert!(!component.value.contains("/nonexistent/mount"));
+        assert!(!component.value.contains("/proc"));
+    }
+
+    #[test]
+    fn test_disk_convert_with_plain_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let directory = dir.path().join("usb");
+        fs::create_dir(&directory).unwrap();
 
-        assert!(result.is_err());
+        let mut disk = Disk {
+            units: Some(vec![directory.to_string_lossy().to_string()]),
+            format: Some(String::from("{mount} {percentage}")),
+            ..Default::default()
+        };
+
+        let component = disk.convert().unwrap();
+
+        assert!(!component.is_hidden());
+        assert!(component.value.ends_with('%'));
+    }
+
+    #[test]
+    fn test_mount_type() {
+        let mountinfo = "\
+22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+35 22 259:2 /srv/data /mnt/bind rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+40 22 0:38 / /mnt/usb rw,relatime shared:20 - autofs systemd-1 rw,fd=45
+41 22 0:39 / /mnt/nas rw,relatime shared:21 - autofs systemd-1 rw,fd=46
+52 41 0:52 / /mnt/nas rw,relatime shared:30 - nfs4 nas:/export rw
+60 22 8:17 / /run/media/user/My\\040Disk rw,nosuid shared:40 - vfat /dev/sdb1 rw
+";
+
+        assert_eq!(mount_type(mountinfo, "/"), Some("ext4"));
+        assert_eq!(mount_type(mountinfo, "/mnt/bind"), Some("ext4"));
+        assert_eq!(mount_type(mountinfo, "/mnt/usb"), Some("autofs"));
+        assert_eq!(mount_type(mountinfo, "/mnt/nas"), Some("nfs4"));
+        assert_eq!(
+            mount_type(mountinfo, "/run/media/user/My Disk"),
+            Some("vfat")
+        );
+        assert_eq!(mount_type(mountinfo, "/home"), None);
+
+        let unmounted = "40 1 0:38 / / rw,relatime shared:20 - autofs systemd-1 rw,fd=45";
+        assert!(usage("/", mountinfo).is_some());
+        assert!(usage("/", unmounted).is_none());
+    }
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!(percentage(25, 100), "25%");
+        assert_eq!(percentage(0, 0), "-");
     }
 }
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, fs, path::Path, time::Instant};
+
+use serde::Deserialize;
+
+use crate::{
+    component::{placeholder, section::Component},
+    configuration::device::Converter,
+};
+
+const NAME: &str = "IO";
+const ICON: &str = "󰓅 ";
+const TIME: u64 = 1000;
+const FORMAT: &str = "{read} {write}";
+const DISKSTATS: &str = "/proc/diskstats";
+const BLOCK: &str = "/sys/block";
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Deserialize, Debug)]
+pub struct DiskIo {
+    pub time: Option<u64>,
+    pub name: Option<String>,
+    pub icon: Option<String>,
+    pub device: Option<String>,
+    pub format: Option<String>,
+    #[serde(skip)]
+    previous: Option<(Instant, HashMap<String, DiskStats>)>,
+}
+
+/// Cumulative counters of a /proc/diskstats line
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct DiskStats {
+    sectors_read: u64,
+    sectors_written: u64,
+    io_ticks: u64,
+}
+
+impl Converter for DiskIo {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let diskstats = fs::read_to_string(DISKSTATS).map_err(anyhow::Error::msg)?;
+        let current: HashMap<String, DiskStats> = parse_diskstats(&diskstats)
+            .into_iter()
+            .filter(|(name, _)| match &self.device {
+                Some(device) => name == device,
+                None => is_physical(Path::new(BLOCK), name),
+            })
+            .collect();
+
+        if current.is_empty() {
+            match &self.device {
+                Some(device) => anyhow::bail!("Block device {device} not found in {DISKSTATS}"),
+                None => anyhow::bail!("No physical block device found in {DISKSTATS}"),
+            }
+        }
+
+        let now = Instant::now();
+        let (read, write, busy) = match self.previous.take() {
+            Some((instant, previous)) => {
+                let elapsed_ms = now.duration_since(instant).as_millis().max(1) as u64;
+                let (read, write, busy) = rates(&previous, &current, elapsed_ms);
+                (human_rate(read), human_rate(write), format!("{busy:.0}%"))
+            }
+            None => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        self.previous = Some((now, current));
+
+        let value = placeholder::fill(
+            self.format.as_deref().unwrap_or(FORMAT),
+            &[("read", read), ("write", write), ("busy", busy)],
+        );
+        let name = self.name.as_deref().unwrap_or(NAME);
+        let icon = self.icon.as_deref().unwrap_or(ICON);
+
+        Ok(Component { name, icon, value })
+    }
+
+    fn time(&self) -> u64 {
+        self.time.unwrap_or(TIME)
+    }
+}
+
+impl Default for DiskIo {
+    fn default() -> Self {
+        Self {
+            time: Some(TIME),
+            name: Some(String::from(NAME)),
+            icon: Some(String::from(ICON)),
+            device: None,
+            format: Some(String::from(FORMAT)),
+            previous: None,
+        }
+    }
+}
+
+fn parse_diskstats(diskstats: &str) -> HashMap<String, DiskStats> {
+    diskstats
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let field = |i: usize| fields.get(i)?.parse::<u64>().ok();
+            Some((
+                fields.get(2)?.to_string(),
+                DiskStats {
+                    sectors_read: field(5)?,
+                    sectors_written: field(9)?,
+                    io_ticks: field(12)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Whole disks backed by hardware, leaving out partitions, loop, ram and device mapper devices
+fn is_physical(block: &Path, name: &str) -> bool {
+    block.join(name).join("device").exists()
+}
+
+/// Read and write bytes per second summed over the devices, and the busy
+/// percentage of the busiest one
+fn rates(
+    previous: &HashMap<String, DiskStats>,
+    current: &HashMap<String, DiskStats>,
+    elapsed_ms: u64,
+) -> (u64, u64, f64) {
+    let mut read = 0;
+    let mut write = 0;
+    let mut busy: f64 = 0.0;
+
+    for (name, stats) in current {
+        let before = previous.get(name).copied().unwrap_or(*stats);
+        read += stats.sectors_read.saturating_sub(before.sectors_read) * SECTOR_SIZE;
+        write += stats.sectors_written.saturating_sub(before.sectors_written) * SECTOR_SIZE;
+        let ticks = stats.io_ticks.saturating_sub(before.io_ticks);
+        busy = busy.max((ticks as f64 / elapsed_ms as f64 * 100.0).min(100.0));
+    }
+
+    (read * 1000 / elapsed_ms, write * 1000 / elapsed_ms, busy)
+}
+
+fn human_rate(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= MIB {
+        format!("{:.1}MiB/s", bytes / MIB)
+    } else {
+        format!("{:.0}KiB/s", bytes / KIB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const DISKSTATS_SAMPLE: &str =
+        "   8       0 sda 1000 10 20000 500 2000 20 40000 900 0 1500 1400 0 0 0 0
+   8       1 sda1 900 10 18000 450 1900 20 38000 850 0 1400 1300 0 0 0 0
+   7       0 loop0 50 0 100 5 0 0 0 0 0 10 5 0 0 0 0
+";
+
+    #[test]
+    fn test_diskio_get_time() {
+        let diskio = DiskIo {
+            time: Some(2000),
+            ..Default::default()
+        };
+        assert_eq!(diskio.time(), 2000);
+
+        let diskio_default = DiskIo::default();
+        assert_eq!(diskio_default.time(), TIME);
+    }
+
+    #[test]
+    fn test_parse_diskstats() {
+        let stats = parse_diskstats(DISKSTATS_SAMPLE);
+
+        assert_eq!(stats.len(), 3);
+        assert_eq!(
+            stats["sda"],
+            DiskStats {
+                sectors_read: 20000,
+                sectors_written: 40000,
+                io_ticks: 1500,
+            }
+        );
+    }
+
+    #[test]
+    fn test_rates() {
+        let previous = parse_diskstats(DISKSTATS_SAMPLE);
+        let mut current = previous.clone();
+        current.insert(
+            String::from("sda"),
+            DiskStats {
+                sectors_read: 20000 + 2048,
+                sectors_written: 40000 + 4096,
+                io_ticks: 1500 + 250,
+            },
+        );
+
+        let (read, write, busy) = rates(&previous, &current, 1000);
+
+        assert_eq!(read, 1024 * 1024);
+        assert_eq!(write, 2 * 1024 * 1024);
+        assert_eq!(busy, 25.0);
+    }
+
+    #[test]
+    fn test_is_physical() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sda/device")).unwrap();
+        fs::create_dir_all(dir.path().join("loop0")).unwrap();
+
+        assert!(is_physical(dir.path(), "sda"));
+        assert!(!is_physical(dir.path(), "loop0"));
+        assert!(!is_physical(dir.path(), "sda1"));
+    }
+
+    #[test]
+    fn test_human_rate() {
+        assert_eq!(human_rate(0), "0KiB/s");
+        assert_eq!(human_rate(512 * 1024), "512KiB/s");
+        assert_eq!(human_rate(3 * 1024 * 1024 / 2), "1.5MiB/s");
+    }
+
+    #[test]
+    fn test_diskio_convert_with_invalid_device() {
+        let mut diskio = DiskIo {
+            device: Some(String::from("invalid_device")),
+            ..Default::default()
+        };
+
+        assert!(diskio.convert().is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{
+    component::{placeholder, section::Component},
+    configuration::device::Converter,
+};
+
+use super::hwmon::{self, HWMON};
+
+const NAME: &str = "FAN";
+const ICON: &str = "󰈐 ";
+const TIME: u64 = 2000;
+const FORMAT: &str = "{max}";
+
+#[derive(Deserialize, Debug)]
+pub struct Fan {
+    pub time: Option<u64>,
+    pub name: Option<String>,
+    pub icon: Option<String>,
+    pub chip: Option<String>,
+    pub label: Option<String>,
+    pub format: Option<String>,
+}
+
+impl Converter for Fan {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let speeds = read_speeds(
+            Path::new(HWMON),
+            self.chip.as_deref(),
+            self.label.as_deref(),
+        );
+
+        let Some(max) = speeds.iter().max() else {
+            return Ok(Component::hidden());
+        };
+
+        let fans = speeds
+            .iter()
+            .map(|rpm| format!("{rpm}RPM"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let value = placeholder::fill(
+            self.format.as_deref().unwrap_or(FORMAT),
+            &[("max", format!("{max}RPM")), ("fans", fans)],
+        );
+        let name = self.name.as_deref().unwrap_or(NAME);
+        let icon = self.icon.as_deref().unwrap_or(ICON);
+
+        Ok(Component { name, icon, value })
+    }
+
+    fn time(&self) -> u64 {
+        self.time.unwrap_or(TIME)
+    }
+}
+
+impl Default for Fan {
+    fn default() -> Self {
+        Self {
+            time: Some(TIME),
+            name: Some(String::from(NAME)),
+            icon: Some(String::from(ICON)),
+            chip: None,
+            label: None,
+            format: Some(String::from(FORMAT)),
+        }
+    }
+}
+
+/// Speeds in RPM of the selected fans, ordered by chip and fan number
+fn read_speeds(hwmon: &Path, chip: Option<&str>, label: Option<&str>) -> Vec<i64> {
+    hwmon::sensors(hwmon, "fan", chip, label)
+        .iter()
+        .filter_map(hwmon::Sensor::input)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fan_get_time() {
+        let fan = Fan {
+            time: Some(5000),
+            ..Default::default()
+        };
+        assert_eq!(fan.time(), 5000);
+
+        let fan_default = Fan::default();
+        assert_eq!(fan_default.time(), TIME);
+    }
+
+    #[test]
+    fn test_read_speeds() {
+        let dir = tempdir().unwrap();
+        hwmon::create_chip(
+            dir.path(),
+            "hwmon3",
+            "thinkpad",
+            &[
+                ("fan1_input", "2400"),
+                ("fan2_input", "0"),
+                ("temp1_input", "50000"),
+            ],
+        );
+        hwmon::create_chip(
+            dir.path(),
+            "hwmon4",
+            "nct6775",
+            &[("fan1_input", "900"), ("fan1_label", "CPU Fan")],
+        );
+
+        assert_eq!(read_speeds(dir.path(), None, None), vec![900, 2400, 0]);
+        assert_eq!(
+            read_speeds(dir.path(), Some("thinkpad"), None),
+            vec![2400, 0]
+        );
+        assert_eq!(read_speeds(dir.path(), None, Some("CPU Fan")), vec![900]);
+        assert!(read_speeds(dir.path(), Some("amdgpu"), None).is_empty());
+    }
+
+    #[test]
+    fn test_fan_convert() {
+        let mut fan = Fan::default();
+
+        let component = fan.convert().unwrap();
+
+        assert!(component.is_hidden() || component.value.ends_with("RPM"));
+    }
+}
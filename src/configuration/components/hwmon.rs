@@ -0,0 +1,134 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const HWMON: &str = "/sys/class/hwmon";
+
+/// Input of a hwmon chip, such as `temp1_input` or `fan2_input`
+#[derive(Debug, PartialEq)]
+pub struct Sensor {
+    pub chip: String,
+    pub label: String,
+    directory: PathBuf,
+    prefix: String,
+}
+
+impl Sensor {
+    pub fn input(&self) -> Option<i64> {
+        self.attribute("input")
+    }
+
+    /// Reads a sibling attribute of the input, e.g. `crit` for `temp1_crit`
+    pub fn attribute(&self, attribute: &str) -> Option<i64> {
+        fs::read_to_string(self.directory.join(format!("{}_{attribute}", self.prefix)))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+}
+
+/// Sensors of a kind (`temp`, `fan`, ...) of every hwmon chip, optionally
+/// filtered by chip name and label. Unlabeled inputs use their prefix as label
+pub fn sensors(hwmon: &Path, kind: &str, chip: Option<&str>, label: Option<&str>) -> Vec<Sensor> {
+    let mut sensors: Vec<Sensor> = fs::read_dir(hwmon)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|directory| {
+            let name = fs::read_to_string(directory.join("name")).ok()?;
+            Some((name.trim().to_string(), directory))
+        })
+        .filter(|(name, _)| chip.is_none_or(|chip| chip == name))
+        .flat_map(|(name, directory)| chip_sensors(&directory, &name, kind))
+        .filter(|sensor| label.is_none_or(|label| label == sensor.label))
+        .collect();
+
+    sensors.sort_by(|a, b| (&a.chip, &a.prefix).cmp(&(&b.chip, &b.prefix)));
+    sensors
+}
+
+fn chip_sensors(directory: &Path, chip: &str, kind: &str) -> Vec<Sensor> {
+    fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let prefix = file_name.strip_suffix("_input")?;
+            let index = prefix.strip_prefix(kind)?;
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+
+            let label = fs::read_to_string(directory.join(format!("{prefix}_label")))
+                .map(|label| label.trim().to_string())
+                .unwrap_or_else(|_| prefix.to_string());
+
+            Some(Sensor {
+                chip: chip.to_string(),
+                label,
+                directory: directory.to_path_buf(),
+                prefix: prefix.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Creates a hwmon chip directory with its sensor files for tests
+#[cfg(test)]
+pub fn create_chip(hwmon: &Path, directory: &str, chip: &str, files: &[(&str, &str)]) {
+    let directory = hwmon.join(directory);
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("name"), format!("{chip}\n")).unwrap();
+    for (file, content) in files {
+        fs::write(directory.join(file), format!("{content}\n")).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sensors() {
+        let dir = tempdir().unwrap();
+        create_chip(
+            dir.path(),
+            "hwmon0",
+            "k10temp",
+            &[
+                ("temp1_input", "55000"),
+                ("temp1_label", "Tctl"),
+                ("temp3_input", "48000"),
+                ("temp3_label", "Tccd1"),
+            ],
+        );
+        create_chip(
+            dir.path(),
+            "hwmon1",
+            "nvme",
+            &[("temp1_input", "38000"), ("fan1_input", "1200")],
+        );
+
+        let all = sensors(dir.path(), "temp", None, None);
+        assert_eq!(all.len(), 3);
+
+        let tctl = sensors(dir.path(), "temp", Some("k10temp"), Some("Tctl"));
+        assert_eq!(tctl.len(), 1);
+        assert_eq!(tctl[0].input(), Some(55000));
+
+        let nvme = sensors(dir.path(), "temp", Some("nvme"), None);
+        assert_eq!(nvme.len(), 1);
+        assert_eq!(nvme[0].label, "temp1");
+
+        let fans = sensors(dir.path(), "fan", None, None);
+        assert_eq!(fans.len(), 1);
+        assert_eq!(fans[0].chip, "nvme");
+
+        assert!(sensors(dir.path(), "temp", Some("coretemp"), None).is_empty());
+    }
+}
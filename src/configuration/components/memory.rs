@@ -1,64 +1,62 @@
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{collections::HashMap, fs};
 
 use serde::Deserialize;
 
-use crate::{component::section::Component, configuration::device::Converter};
+use crate::{
+    component::{
+        placeholder::{self, human_bytes},
+        section::Component,
+    },
+    configuration::device::Converter,
+};
 
 const NAME: &str = "RAM";
 const ICON: &str = " ";
 const TIME: u64 = 1000;
+const FORMAT: &str = "{percentage}";
+const MEMINFO: &str = "/proc/meminfo";
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// MemTotal - MemAvailable, as reported by the kernel
+    #[default]
+    Available,
+    /// MemTotal - MemFree - Buffers - Cached
+    Legacy,
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Memory {
     pub time: Option<u64>,
     pub name: Option<String>,
     pub icon: Option<String>,
+    pub format: Option<String>,
+    pub method: Option<Method>,
 }
 
 impl Converter for Memory {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let file = File::open("/proc/meminfo").map_err(anyhow::Error::msg)?;
-        let reader = BufReader::new(file);
-
-        let mut mem_total = 0u64;
-        let mut mem_free = 0u64;
-        let mut buffers = 0u64;
-        let mut cached = 0u64;
-
-        for line in reader.lines() {
-            let line = line.map_err(anyhow::Error::msg)?;
-            if line.starts_with("MemTotal:") {
-                mem_total = parse_meminfo_line(&line)?;
-            }
-            if line.starts_with("MemFree:") {
-                mem_free = parse_meminfo_line(&line)?;
-            }
-            if line.starts_with("Buffers:") {
-                buffers = parse_meminfo_line(&line)?;
-            }
-            if line.starts_with("Cached:") {
-                cached = parse_meminfo_line(&line)?;
-            }
-        }
-
-        if mem_total == 0 || mem_free == 0 {
-            anyhow::bail!("Failed to read MemTotal and/or MemFree from /proc/meminfo");
-        }
-
-        let used = mem_total - mem_free - buffers - cached;
-        let total = (used as f64 / mem_total as f64) * 100.0;
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let meminfo = fs::read_to_string(MEMINFO).map_err(anyhow::Error::msg)?;
+        let meminfo = parse_meminfo(&meminfo);
+
+        let (used, total) = used_memory(&meminfo, self.method.unwrap_or_default())?;
+        let percentage = (used as f64 / total as f64) * 100.0;
+
+        let value = placeholder::fill(
+            self.format.as_deref().unwrap_or(FORMAT),
+            &[
+                ("percentage", format!("{percentage:.0}%")),
+                ("used", human_bytes(used)),
+                ("total", human_bytes(total)),
+                ("available", human_bytes(total.saturating_sub(used))),
+            ],
+        );
 
         let name = self.name.as_deref().unwrap_or(NAME);
         let icon = self.icon.as_deref().unwrap_or(ICON);
 
-        Ok(Component {
-            name,
-            icon,
-            value: format!("{total:.0}%"),
-        })
+        Ok(Component { name, icon, value })
     }
 
     fn time(&self) -> u64 {
@@ -72,17 +70,45 @@ impl Default for Memory {
             time: Some(TIME),
             name: Some(String::from(NAME)),
             icon: Some(String::from(ICON)),
+            format: Some(String::from(FORMAT)),
+            method: Some(Method::Available),
         }
     }
 }
 
-fn parse_meminfo_line(line: &str) -> anyhow::Result<u64> {
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() < 2 {
-        anyhow::bail!("Invalid meminfo line format: {}", line);
+/// Used and total memory in bytes. Falls back to the legacy method on
+/// kernels not reporting MemAvailable
+fn used_memory(meminfo: &HashMap<String, u64>, method: Method) -> anyhow::Result<(u64, u64)> {
+    let field = |key: &str| meminfo.get(key).copied().unwrap_or(0);
+
+    let mem_total = field("MemTotal");
+    let mem_free = field("MemFree");
+
+    if mem_total == 0 || mem_free == 0 {
+        anyhow::bail!("Failed to read MemTotal and/or MemFree from /proc/meminfo");
     }
-    let value = parts[1].parse::<u64>().map_err(anyhow::Error::msg)?;
-    Ok(value)
+
+    let used = match meminfo.get("MemAvailable") {
+        Some(available) if method == Method::Available => mem_total.saturating_sub(*available),
+        _ => mem_total
+            .saturating_sub(mem_free)
+            .saturating_sub(field("Buffers"))
+            .saturating_sub(field("Cached")),
+    };
+
+    Ok((used * 1024, mem_total * 1024))
+}
+
+/// Values of /proc/meminfo in kB by field name. Lines that cannot be parsed
+/// are skipped
+pub(crate) fn parse_meminfo(meminfo: &str) -> HashMap<String, u64> {
+    meminfo.lines().filter_map(parse_meminfo_line).collect()
+}
+
+fn parse_meminfo_line(line: &str) -> Option<(String, u64)> {
+    let (key, value) = line.split_once(':')?;
+    let value = value.split_whitespace().next()?.parse::<u64>().ok()?;
+    Some((key.trim().to_string(), value))
 }
 
 #[cfg(test)]
@@ -95,6 +121,8 @@ mod tests {
             time: Some(2000),
             name: None,
             icon: None,
+            format: None,
+            method: None,
         };
         assert_eq!(memory.time(), 2000);
 
@@ -104,10 +132,12 @@ mod tests {
 
     #[test]
     fn test_memory_convert() {
-        let memory = Memory {
+        let mut memory = Memory {
             time: Some(1000),
             name: Some(String::from("Custom RAM")),
             icon: Some(String::from(ICON)),
+            format: None,
+            method: None,
         };
 
         let component = memory.convert().unwrap();
@@ -119,7 +149,7 @@ mod tests {
 
     #[test]
     fn test_memory_convert_with_default_values() {
-        let memory = Memory::default();
+        let mut memory = Memory::default();
 
         let component = memory.convert().unwrap();
 
@@ -127,4 +157,65 @@ mod tests {
         assert_eq!(component.icon, ICON);
         assert!(component.value.ends_with("%"));
     }
+
+    const MEMINFO_SAMPLE: &str = "MemTotal:       16000000 kB
+MemFree:         2000000 kB
+MemAvailable:    8000000 kB
+Buffers:          500000 kB
+Cached:          6000000 kB
+Shmem:           1000000 kB
+SReclaimable:     400000 kB
+";
+
+    #[test]
+    fn test_used_memory_with_available_method() {
+        let meminfo = parse_meminfo(MEMINFO_SAMPLE);
+
+        let (used, total) = used_memory(&meminfo, Method::Available).unwrap();
+
+        assert_eq!(used, 8000000 * 1024);
+        assert_eq!(total, 16000000 * 1024);
+    }
+
+    #[test]
+    fn test_used_memory_with_legacy_method() {
+        let meminfo = parse_meminfo(MEMINFO_SAMPLE);
+
+        let (used, _) = used_memory(&meminfo, Method::Legacy).unwrap();
+
+        assert_eq!(used, 7500000 * 1024);
+    }
+
+    #[test]
+    fn test_used_memory_does_not_underflow() {
+        let meminfo =
+            parse_meminfo("MemTotal: 1000 kB\nMemFree: 600 kB\nBuffers: 300 kB\nCached: 300 kB\n");
+
+        let (used, _) = used_memory(&meminfo, Method::Legacy).unwrap();
+
+        assert_eq!(used, 0);
+    }
+
+    #[test]
+    fn test_parse_meminfo_skips_invalid_lines() {
+        let meminfo =
+            parse_meminfo("MemTotal: 1000 kB\nBogus\nHugePages_Total: n/a\n\nMemFree: 600 kB\n");
+
+        assert_eq!(meminfo.len(), 2);
+        assert_eq!(meminfo.get("MemTotal"), Some(&1000));
+        assert_eq!(meminfo.get("MemFree"), Some(&600));
+    }
+
+    #[test]
+    fn test_memory_convert_with_absolute_values() {
+        let mut memory = Memory {
+            format: Some(String::from("{used}/{total} {available}")),
+            ..Default::default()
+        };
+
+        let component = memory.convert().unwrap();
+
+        assert!(component.value.contains('/'));
+        assert!(component.value.contains("iB"));
+    }
 }
@@ -0,0 +1,248 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alsa::mixer::{Selem, SelemChannelId};
+use serde::Deserialize;
+
+use crate::{
+    component::{placeholder, section::Component},
+    configuration::device::Converter,
+};
+
+use super::volume::{find_selem, open_mixer};
+
+const NAME: &str = "MIC";
+const ICON_ACTIVE: &str = "󰍬 ";
+const ICON_MUTED: &str = "󰍭 ";
+const TIME: u64 = 1000;
+const MUTED: &str = "MUTED";
+const FORMAT: &str = "{volume}";
+const CARD: &str = "default";
+const CONTROLS: [&str; 4] = ["Capture", "Mic", "Internal Mic", "Digital"];
+const ASOUND: &str = "/proc/asound";
+
+#[derive(Deserialize, Debug)]
+pub struct Microphone {
+    pub time: Option<u64>,
+    pub name: Option<String>,
+    pub icon_active: Option<String>,
+    pub icon_muted: Option<String>,
+    pub format: Option<String>,
+    pub card: Option<String>,
+    pub control: Option<String>,
+    pub index: Option<u32>,
+    pub only_when_active: Option<bool>,
+}
+
+impl Converter for Microphone {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let card = self.card.as_deref().unwrap_or(CARD);
+        if self.only_when_active.unwrap_or(false) && !is_capturing(Path::new(ASOUND), card) {
+            return Ok(Component::hidden());
+        }
+
+        let mixer = open_mixer(card)?;
+        let elem = find_selem(
+            &mixer,
+            self.control.as_deref(),
+            self.index.unwrap_or(0),
+            &CONTROLS,
+            |selem| selem.has_capture_volume() || selem.has_capture_switch(),
+        )?;
+
+        let channels = capture_channels(&elem);
+        if channels.is_empty() {
+            anyhow::bail!("Mixer control has no capture channels");
+        }
+
+        let is_muted = if elem.has_capture_switch() {
+            let switches = channels
+                .iter()
+                .map(|channel| elem.get_capture_switch(*channel))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::msg)?;
+            switches.iter().all(|switch| *switch == 0)
+        } else {
+            false
+        };
+
+        let icon = if is_muted {
+            self.icon_muted.as_deref().unwrap_or(ICON_MUTED)
+        } else {
+            self.icon_active.as_deref().unwrap_or(ICON_ACTIVE)
+        };
+
+        let volume = if is_muted {
+            String::from(MUTED)
+        } else if elem.has_capture_volume() {
+            let (min, max) = elem.get_capture_volume_range();
+            let volumes = channels
+                .iter()
+                .map(|channel| elem.get_capture_volume(*channel))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::msg)?;
+            let volume = volumes.iter().sum::<i64>() as f64 / volumes.len() as f64;
+
+            let volume_percentage = if max > min {
+                ((volume - min as f64) / (max - min) as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            format!("{volume_percentage:.0}%")
+        } else {
+            String::new()
+        };
+
+        let value = placeholder::fill(
+            self.format.as_deref().unwrap_or(FORMAT),
+            &[("volume", &volume)],
+        );
+        let name = self.name.as_deref().unwrap_or(NAME);
+
+        Ok(Component { name, icon, value })
+    }
+
+    fn time(&self) -> u64 {
+        self.time.unwrap_or(TIME)
+    }
+}
+
+impl Default for Microphone {
+    fn default() -> Self {
+        Self {
+            time: Some(TIME),
+            name: Some(String::from(NAME)),
+            icon_active: Some(String::from(ICON_ACTIVE)),
+            icon_muted: Some(String::from(ICON_MUTED)),
+            format: Some(String::from(FORMAT)),
+            card: Some(String::from(CARD)),
+            control: None,
+            index: Some(0),
+            only_when_active: Some(false),
+        }
+    }
+}
+
+fn capture_channels(elem: &Selem) -> Vec<SelemChannelId> {
+    if elem.is_capture_mono() {
+        return vec![SelemChannelId::mono()];
+    }
+
+    SelemChannelId::all()
+        .iter()
+        .copied()
+        .filter(|c| *c != SelemChannelId::Unknown && *c != SelemChannelId::Last)
+        .filter(|c| elem.has_capture_channel(*c))
+        .collect()
+}
+
+/// Whether any capture substream (`card*/pcm*c/sub*/status`) of the card is
+/// open. Cards that are not a hardware card, like `default`, check every card
+fn is_capturing(asound: &Path, card: &str) -> bool {
+    let cards = match card_directory(asound, card) {
+        Some(directory) => vec![directory],
+        None => entries(asound, |name| name.starts_with("card")),
+    };
+
+    cards
+        .into_iter()
+        .flat_map(|card| entries(&card, |name| name.starts_with("pcm") && name.ends_with('c')))
+        .flat_map(|pcm| entries(&pcm, |name| name.starts_with("sub")))
+        .filter_map(|sub| fs::read_to_string(sub.join("status")).ok())
+        .any(|status| status.trim() != "closed")
+}
+
+/// Directory of a hardware card given by index or id, such as `hw:0`,
+/// `hw:PCH` or `hw:CARD=PCH`. Named cards are links to their `cardN` directory
+fn card_directory(asound: &Path, card: &str) -> Option<PathBuf> {
+    let card = card.strip_prefix("hw:")?;
+    let card = card.strip_prefix("CARD=").unwrap_or(card);
+    let card = card.split(',').next().unwrap_or(card);
+
+    if card.is_empty() {
+        None
+    } else if card.chars().all(|c| c.is_ascii_digit()) {
+        Some(asound.join(format!("card{card}")))
+    } else {
+        Some(asound.join(card))
+    }
+}
+
+fn entries(dir: &Path, predicate: fn(&str) -> bool) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| predicate(&entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_status(asound: &Path, substream: &str, status: &str) {
+        let dir = asound.join(substream);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("status"), status).unwrap();
+    }
+
+    #[test]
+    fn test_microphone_get_time() {
+        let microphone = Microphone {
+            time: Some(2000),
+            name: None,
+            icon_active: None,
+            icon_muted: None,
+            format: None,
+            card: None,
+            control: None,
+            index: None,
+            only_when_active: None,
+        };
+        assert_eq!(microphone.time(), 2000);
+
+        let microphone_default = Microphone::default();
+        assert_eq!(microphone_default.time(), TIME);
+    }
+
+    #[test]
+    fn test_microphone_convert_with_invalid_card() {
+        let mut microphone = Microphone {
+            card: Some(String::from("hw:99")),
+            ..Default::default()
+        };
+
+        assert!(microphone.convert().is_err());
+    }
+
+    #[test]
+    fn test_is_capturing() {
+        let dir = tempdir().unwrap();
+        let asound = dir.path();
+
+        assert!(!is_capturing(asound, CARD));
+
+        create_status(asound, "card0/pcm0p/sub0", "state: RUNNING\n");
+        create_status(asound, "card0/pcm0c/sub0", "closed\n");
+        assert!(!is_capturing(asound, CARD));
+
+        create_status(
+            asound,
+            "card1/pcm2c/sub1",
+            "state: RUNNING\nowner_pid   : 42\n",
+        );
+        assert!(is_capturing(asound, CARD));
+        assert!(is_capturing(asound, "hw:1"));
+        assert!(!is_capturing(asound, "hw:0"));
+
+        std::os::unix::fs::symlink(asound.join("card0"), asound.join("PCH")).unwrap();
+        assert!(!is_capturing(asound, "hw:PCH"));
+        assert!(!is_capturing(asound, "hw:CARD=PCH,DEV=0"));
+    }
+}
@@ -2,10 +2,15 @@ pub mod battery;
 pub mod cpu;
 pub mod date;
 pub mod disk;
+pub mod diskio;
+pub mod fan;
 pub mod general;
+pub mod hwmon;
 pub mod memory;
+pub mod microphone;
 pub mod network;
 pub mod script;
+pub mod swap;
 pub mod temperature;
 pub mod volume;
 pub mod weather;
@@ -21,7 +21,7 @@ pub struct Network {
 }
 
 impl Converter for Network {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
         let address: SocketAddr = "8.8.8.8:53".parse().expect("Invalid address");
 
         let timeout = Duration::from_secs(60);
@@ -83,7 +83,7 @@ mod tests {
 
     #[test]
     fn test_network_convert_success() {
-        let network = Network {
+        let mut network = Network {
             time: Some(1000),
             name: Some(String::from("Custom Network")),
             icon_up: Some(String::from(ICON_UP)),
@@ -1,42 +1,139 @@
-use std::process::Command;
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read},
+    os::unix::process::CommandExt,
+    process::{self, Child, Command, ExitStatus, Output, Stdio},
+    sync::Mutex,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
 use serde::Deserialize;
 
-use crate::{component::section::Component, configuration::device::Converter};
+use crate::{
+    component::section::Component,
+    configuration::device::{Converter, Watcher},
+};
 
 const NAME: &str = "SCR";
 const ICON: &str = "󰯁 ";
 const TIME: u64 = 1000;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const ERROR_TEXT: &str = "Error";
+/// Exit code of an urgent script, as in i3blocks
+const URGENT_CODE: i32 = 33;
+
+/// Process groups of the running stream scripts
+static STREAMS: Mutex<Vec<libc::pid_t>> = Mutex::new(Vec::new());
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Runs the script every `time` miliseconds
+    #[default]
+    Interval,
+    /// Runs the script once and shows every line it prints
+    Stream,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// The output is the value of the component
+    #[default]
+    Text,
+    /// The output is a JSON object with text, icon and state
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum State {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// What a script prints with `output = "json"`. Other fields, such as
+/// `tooltip`, are accepted and ignored
+#[derive(Deserialize, Debug, Default)]
+struct Block {
+    #[serde(alias = "full_text")]
+    text: String,
+    icon: Option<String>,
+    state: Option<String>,
+    #[serde(default)]
+    urgent: bool,
+}
+
+impl Block {
+    fn state(&self) -> State {
+        match self.state.as_deref() {
+            _ if self.urgent => State::Critical,
+            Some("critical" | "urgent") => State::Critical,
+            Some("warning") => State::Warning,
+            _ => State::Normal,
+        }
+    }
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Script {
     pub time: Option<u64>,
     pub name: Option<String>,
     pub icon: Option<String>,
+    pub icon_warning: Option<String>,
+    pub icon_critical: Option<String>,
+    /// Shell script run with `sh`
+    #[serde(default)]
     pub path: String,
+    /// Program and arguments, run instead of `path`
+    pub command: Option<Vec<String>>,
+    pub env: Option<HashMap<String, String>>,
+    pub cwd: Option<String>,
+    /// Miliseconds after which the script is killed
+    pub timeout: Option<u64>,
+    pub mode: Option<Mode>,
+    pub output: Option<OutputFormat>,
+    /// Exit code that hides the component
+    pub hide_code: Option<i32>,
+    /// Value shown when the script fails
+    pub error_text: Option<String>,
+    /// Icon printed by the script
+    #[serde(skip)]
+    block_icon: Option<String>,
 }
 
 impl Converter for Script {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let name = self.name.as_deref().unwrap_or(NAME);
-        let icon = self.icon.as_deref().unwrap_or(ICON);
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let output = self.output()?;
 
-        let output = Command::new("sh")
-            .arg(&self.path)
-            .output()
-            .map_err(anyhow::Error::msg)?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && !stderr.trim().is_empty() {
+            eprintln!("Script {}: {}", output.status, stderr.trim_end());
+        }
+
+        let code = output.status.code();
+        if code.is_some() && code == self.hide_code {
+            return Ok(Component::hidden());
+        }
 
-        let temp = if output.status.success() {
-            String::from_utf8_lossy(&output.stdout).to_string()
-        } else {
-            format!("Error {}", String::from_utf8_lossy(&output.stderr))
+        let block = match code {
+            Some(0) => self.parse(&String::from_utf8_lossy(&output.stdout)),
+            Some(URGENT_CODE) => Block {
+                urgent: true,
+                ..self.parse(&String::from_utf8_lossy(&output.stdout))
+            },
+            _ => Block {
+                text: self
+                    .error_text
+                    .clone()
+                    .unwrap_or_else(|| String::from(ERROR_TEXT)),
+                ..Default::default()
+            },
         };
 
-        Ok(Component {
-            name,
-            icon,
-            value: temp,
-        })
+        Ok(self.component(block))
     }
 
     fn time(&self) -> u64 {
@@ -44,22 +141,281 @@ impl Converter for Script {
     }
 }
 
+/// Stream mode. The script is restarted when it exits, waiting twice as long
+/// after every quick exit. A script running for longer than the maximum wait
+/// starts over from `time`
+impl Watcher for Script {
+    fn watch(&mut self, notify: &mut dyn FnMut(Component<'_>)) -> anyhow::Result<()> {
+        let initial = Duration::from_millis(self.time());
+        let mut backoff = initial;
+        loop {
+            let start = Instant::now();
+            if let Err(e) = self.stream(notify) {
+                eprintln!("Script stream error: {e}");
+            }
+            if start.elapsed() >= MAX_BACKOFF {
+                backoff = initial;
+            }
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+impl Script {
+    pub fn is_stream(&self) -> bool {
+        self.mode == Some(Mode::Stream)
+    }
+
+    fn parse(&self, output: &str) -> Block {
+        let output = output.trim_end();
+        if self.output.unwrap_or_default() == OutputFormat::Json {
+            match serde_json::from_str(output) {
+                Ok(block) => return block,
+                Err(e) => eprintln!("Script JSON output error: {e}"),
+            }
+        }
+
+        Block {
+            text: output.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The icon printed by the script goes before the icon of its state
+    fn component(&mut self, block: Block) -> Component<'_> {
+        let state = block.state();
+        self.block_icon = block.icon;
+
+        let default = self.icon.as_deref().unwrap_or(ICON);
+        let icon = match (&self.block_icon, state) {
+            (Some(icon), _) => icon.as_str(),
+            (None, State::Critical) => self.icon_critical.as_deref().unwrap_or(default),
+            (None, State::Warning) => self.icon_warning.as_deref().unwrap_or(default),
+            (None, State::Normal) => default,
+        };
+        let name = self.name.as_deref().unwrap_or(NAME);
+
+        Component {
+            name,
+            icon,
+            value: block.text,
+        }
+    }
+
+    /// Runs the script until it exits, notifying every line of its output
+    fn stream(&mut self, notify: &mut dyn FnMut(Component<'_>)) -> anyhow::Result<()> {
+        let mut command = self.command()?;
+        // SAFETY: prctl is async-signal-safe. The script gets SIGTERM if
+        // rstatusbar is killed before it can kill the process group
+        unsafe {
+            command.pre_exec(|| {
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(anyhow::Error::msg)?;
+        let _group = StreamGroup::new(&child);
+
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).split(b'\n') {
+                let Ok(line) = line else {
+                    break;
+                };
+                let block = self.parse(&String::from_utf8_lossy(&line));
+                notify(self.component(block));
+            }
+        }
+
+        let status = child.wait().map_err(anyhow::Error::msg)?;
+        if status.code().is_some() && status.code() == self.hide_code {
+            notify(Component::hidden());
+        }
+        anyhow::bail!("Script exited with {status}")
+    }
+
+    fn command(&self) -> anyhow::Result<Command> {
+        let mut command = match &self.command {
+            Some(argv) => {
+                let (program, args) = argv
+                    .split_first()
+                    .ok_or_else(|| anyhow::anyhow!("Script command is empty"))?;
+                let mut command = Command::new(program);
+                command.args(args);
+                command
+            }
+            None if !self.path.is_empty() => {
+                let mut command = Command::new("sh");
+                command.arg(&self.path);
+                command
+            }
+            None => anyhow::bail!("Script requires a path or a command"),
+        };
+
+        if let Some(env) = &self.env {
+            command.envs(env);
+        }
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+        // Own process group, so a timeout also kills the children of the script
+        command.stdin(Stdio::null()).process_group(0);
+
+        Ok(command)
+    }
+
+    fn output(&self) -> anyhow::Result<Output> {
+        let mut child = self
+            .command()?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(anyhow::Error::msg)?;
+
+        let stdout = read_pipe(child.stdout.take());
+        let stderr = read_pipe(child.stderr.take());
+
+        let status = match self.timeout {
+            Some(timeout) => wait_timeout(&mut child, Duration::from_millis(timeout))?,
+            None => child.wait().map_err(anyhow::Error::msg)?,
+        };
+
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+}
+
 impl Default for Script {
     fn default() -> Self {
         Self {
             time: Some(TIME),
             name: Some(String::from(NAME)),
             icon: Some(String::from(ICON)),
+            icon_warning: None,
+            icon_critical: None,
             path: String::from(""),
+            command: None,
+            env: None,
+            cwd: None,
+            timeout: None,
+            mode: None,
+            output: None,
+            hide_code: None,
+            error_text: None,
+            block_icon: None,
         }
     }
 }
 
+/// Reads a pipe until it is closed, so the child never blocks on a full pipe
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+fn wait_timeout(child: &mut Child, timeout: Duration) -> anyhow::Result<ExitStatus> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(anyhow::Error::msg)? {
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout {
+            kill_group(child);
+            anyhow::bail!("Script timed out after {} ms", timeout.as_millis());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn kill_group(child: &mut Child) {
+    kill_pgid(child.id() as libc::pid_t);
+    let _ = child.wait();
+}
+
+fn kill_pgid(pgid: libc::pid_t) {
+    // SAFETY: kill has no memory safety requirements. The negative pid targets
+    // the process group created for the child
+    unsafe { libc::kill(-pgid, libc::SIGKILL) };
+}
+
+/// Process group of a stream script, killed with the children the script
+/// leaves behind when the stream ends
+struct StreamGroup(libc::pid_t);
+
+impl StreamGroup {
+    fn new(child: &Child) -> Self {
+        let pgid = child.id() as libc::pid_t;
+        if let Ok(mut streams) = STREAMS.lock() {
+            streams.push(pgid);
+        }
+        Self(pgid)
+    }
+}
+
+impl Drop for StreamGroup {
+    fn drop(&mut self) {
+        if let Ok(mut streams) = STREAMS.lock() {
+            streams.retain(|pgid| *pgid != self.0);
+        }
+        kill_pgid(self.0);
+    }
+}
+
+/// Kills the process groups of every running stream script
+pub fn kill_streams() {
+    if let Ok(streams) = STREAMS.lock() {
+        streams.iter().copied().for_each(kill_pgid);
+    }
+}
+
+/// Kills the process groups of the stream scripts when rstatusbar gets
+/// SIGTERM, SIGINT or SIGHUP. It has to run before any other thread is started,
+/// so they inherit the blocked signals and only this thread receives them
+pub fn kill_streams_on_exit() {
+    // SAFETY: the set is initialized by sigemptyset before being used, and
+    // blocking signals has no memory safety requirements
+    let signals = unsafe {
+        let mut signals: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut signals);
+        for signal in [libc::SIGTERM, libc::SIGINT, libc::SIGHUP] {
+            libc::sigaddset(&mut signals, signal);
+        }
+        libc::pthread_sigmask(libc::SIG_BLOCK, &signals, std::ptr::null_mut());
+        signals
+    };
+
+    thread::spawn(move || {
+        let mut signal = 0;
+        // SAFETY: sigwait only reads the initialized set and writes the signal
+        if unsafe { libc::sigwait(&signals, &mut signal) } != 0 {
+            return;
+        }
+        kill_streams();
+        process::exit(128 + signal);
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::{File, metadata, remove_file, set_permissions};
     use std::{io::Write, os::unix::fs::PermissionsExt};
+    use tempfile::tempdir;
 
     #[test]
     fn test_convert_success() {
@@ -72,33 +428,206 @@ mod tests {
         set_permissions(script_path, permissions).unwrap();
         std::mem::drop(file);
 
-        let script = Script {
+        let mut script = Script {
             time: Some(1000),
             name: Some(String::from("Test Script")),
             icon: Some(String::from(ICON)),
             path: String::from(script_path),
+            ..Default::default()
         };
 
         let component = script.convert().unwrap();
 
         assert_eq!(component.name, "Test Script");
         assert_eq!(component.icon, ICON);
-        assert_eq!(component.value, "Hello, World!\n");
+        assert_eq!(component.value, "Hello, World!");
 
         remove_file(script_path).unwrap();
     }
 
     #[test]
     fn test_convert_failure() {
-        let script = Script {
+        let mut script = Script {
             time: Some(1000),
             name: Some(String::from("Nonexistent Script")),
             icon: Some(String::from("❌")),
             path: String::from("/nonexistent/path"),
+            ..Default::default()
         };
 
         let component = script.convert().unwrap();
 
         assert!(component.value.contains("Error"));
     }
+
+    #[test]
+    fn test_convert_command_with_env_and_cwd() {
+        let dir = tempdir().unwrap();
+        let mut script = Script {
+            command: Some(vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("printf '%s %s\\n\\n' \"$GREETING\" \"$(pwd)\""),
+            ]),
+            env: Some(HashMap::from([(
+                String::from("GREETING"),
+                String::from("hi"),
+            )])),
+            cwd: Some(dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let component = script.convert().unwrap();
+
+        let cwd = dir.path().canonicalize().unwrap();
+        assert_eq!(component.value, format!("hi {}", cwd.display()));
+    }
+
+    #[test]
+    fn test_convert_timeout_kills_script() {
+        let mut script = Script {
+            command: Some(vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("sleep 5; echo late"),
+            ]),
+            timeout: Some(100),
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let result = script.convert();
+
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_convert_without_path_or_command() {
+        let mut script = Script::default();
+        assert!(script.convert().is_err());
+
+        let mut empty = Script {
+            command: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(empty.convert().is_err());
+    }
+
+    #[test]
+    fn test_stream_notifies_every_line() {
+        let mut script = Script {
+            command: Some(vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("echo one; echo two; exit 3"),
+            ]),
+            mode: Some(Mode::Stream),
+            ..Default::default()
+        };
+        let mut values = Vec::new();
+
+        let result = script.stream(&mut |component| values.push(component.value));
+
+        assert!(script.is_stream());
+        assert!(result.unwrap_err().to_string().contains('3'));
+        assert_eq!(values, vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_stream_kills_children_left_behind() {
+        let mut script = Script {
+            command: Some(vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("sleep 30 > /dev/null & echo $!"),
+            ]),
+            mode: Some(Mode::Stream),
+            ..Default::default()
+        };
+        let mut pid = String::new();
+
+        let _ = script.stream(&mut |component| pid = component.value);
+
+        // A killed process disappears or stays a zombie until it is reaped
+        let start = Instant::now();
+        let is_alive = || {
+            std::fs::read_to_string(format!("/proc/{pid}/stat"))
+                .is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        while is_alive() && start.elapsed() < Duration::from_secs(2) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!pid.is_empty());
+        assert!(!is_alive());
+    }
+
+    #[test]
+    fn test_convert_json_output() {
+        let mut script = Script {
+            command: Some(vec![
+                String::from("echo"),
+                String::from(r#"{"text": "42%", "icon": "X", "tooltip": "ignored"}"#),
+            ]),
+            output: Some(OutputFormat::Json),
+            ..Default::default()
+        };
+
+        let component = script.convert().unwrap();
+
+        assert_eq!(component.icon, "X");
+        assert_eq!(component.value, "42%");
+    }
+
+    #[test]
+    fn test_component_state_icons() {
+        let mut script = Script {
+            icon_warning: Some(String::from("W")),
+            icon_critical: Some(String::from("C")),
+            output: Some(OutputFormat::Json),
+            ..Default::default()
+        };
+
+        let warning = script.parse(r#"{"text": "a", "state": "warning"}"#);
+        assert_eq!(script.component(warning).icon, "W");
+
+        let urgent = script.parse(r#"{"full_text": "b", "urgent": true}"#);
+        assert_eq!(script.component(urgent).icon, "C");
+
+        let normal = script.parse(r#"{"text": "c", "state": "good"}"#);
+        assert_eq!(script.component(normal).icon, ICON);
+
+        let invalid = script.parse("not json\n");
+        let component = script.component(invalid);
+        assert_eq!(component.icon, ICON);
+        assert_eq!(component.value, "not json");
+    }
+
+    #[test]
+    fn test_convert_exit_codes() {
+        let script = |code: i32| Script {
+            command: Some(vec![
+                String::from("sh"),
+                String::from("-c"),
+                format!("echo output; echo 'line 1\nline 2' >&2; exit {code}"),
+            ]),
+            icon_critical: Some(String::from("C")),
+            hide_code: Some(7),
+            error_text: Some(String::from("failed")),
+            ..Default::default()
+        };
+
+        let mut urgent = script(URGENT_CODE);
+        let component = urgent.convert().unwrap();
+        assert_eq!(component.icon, "C");
+        assert_eq!(component.value, "output");
+
+        let mut hidden = script(7);
+        assert!(hidden.convert().unwrap().is_hidden());
+
+        let mut failed = script(1);
+        let component = failed.convert().unwrap();
+        assert_eq!(component.icon, ICON);
+        assert_eq!(component.value, "failed");
+    }
 }
@@ -0,0 +1,151 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    component::{
+        placeholder::{self, human_bytes},
+        section::Component,
+    },
+    configuration::device::Converter,
+};
+
+use super::memory::parse_meminfo;
+
+const NAME: &str = "SWAP";
+const ICON: &str = "󰓡 ";
+const TIME: u64 = 5000;
+const FORMAT: &str = "{percentage}";
+const MEMINFO: &str = "/proc/meminfo";
+const BLOCK: &str = "/sys/block";
+
+#[derive(Deserialize, Debug)]
+pub struct Swap {
+    pub time: Option<u64>,
+    pub name: Option<String>,
+    pub icon: Option<String>,
+    pub format: Option<String>,
+}
+
+impl Converter for Swap {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let meminfo = fs::read_to_string(MEMINFO).map_err(anyhow::Error::msg)?;
+        let meminfo = parse_meminfo(&meminfo);
+
+        let total = meminfo.get("SwapTotal").copied().unwrap_or(0) * 1024;
+        if total == 0 {
+            return Ok(Component::hidden());
+        }
+        let free = meminfo.get("SwapFree").copied().unwrap_or(0) * 1024;
+        let used = total.saturating_sub(free);
+        let percentage = (used as f64 / total as f64) * 100.0;
+
+        let format = self.format.as_deref().unwrap_or(FORMAT);
+        let mut values = vec![
+            ("percentage", format!("{percentage:.0}%")),
+            ("used", human_bytes(used)),
+            ("total", human_bytes(total)),
+        ];
+
+        if format.contains("{zram") {
+            let (original, compressed) = read_zram(Path::new(BLOCK));
+            let ratio = if compressed > 0 {
+                format!("{:.1}x", original as f64 / compressed as f64)
+            } else {
+                "-".to_string()
+            };
+            values.push(("zram_ratio", ratio));
+            values.push(("zram_used", human_bytes(original)));
+            values.push(("zram_compressed", human_bytes(compressed)));
+        }
+
+        let value = placeholder::fill(format, &values);
+        let name = self.name.as_deref().unwrap_or(NAME);
+        let icon = self.icon.as_deref().unwrap_or(ICON);
+
+        Ok(Component { name, icon, value })
+    }
+
+    fn time(&self) -> u64 {
+        self.time.unwrap_or(TIME)
+    }
+}
+
+impl Default for Swap {
+    fn default() -> Self {
+        Self {
+            time: Some(TIME),
+            name: Some(String::from(NAME)),
+            icon: Some(String::from(ICON)),
+            format: Some(String::from(FORMAT)),
+        }
+    }
+}
+
+/// Original and compressed data sizes in bytes summed over every zram device
+fn read_zram(block: &Path) -> (u64, u64) {
+    fs::read_dir(block)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("zram"))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("mm_stat")).ok())
+        .filter_map(|mm_stat| {
+            let mut fields = mm_stat.split_whitespace().map(|f| f.parse::<u64>());
+            Some((fields.next()?.ok()?, fields.next()?.ok()?))
+        })
+        .fold((0, 0), |(original, compressed), (o, c)| {
+            (original + o, compressed + c)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_swap_get_time() {
+        let swap = Swap {
+            time: Some(2000),
+            name: None,
+            icon: None,
+            format: None,
+        };
+        assert_eq!(swap.time(), 2000);
+
+        let swap_default = Swap::default();
+        assert_eq!(swap_default.time(), TIME);
+    }
+
+    #[test]
+    fn test_swap_convert() {
+        let mut swap = Swap::default();
+
+        let component = swap.convert().unwrap();
+
+        assert!(component.is_hidden() || component.value.ends_with('%'));
+    }
+
+    #[test]
+    fn test_read_zram() {
+        let dir = tempdir().unwrap();
+        for (device, mm_stat) in [
+            ("zram0", "3000000 1000000 1200000 0 1500000 0 0 0 0"),
+            ("zram1", "1000000 500000 600000 0 700000 0 0 0 0"),
+        ] {
+            fs::create_dir_all(dir.path().join(device)).unwrap();
+            fs::write(dir.path().join(device).join("mm_stat"), mm_stat).unwrap();
+        }
+        fs::create_dir_all(dir.path().join("sda")).unwrap();
+
+        assert_eq!(read_zram(dir.path()), (4000000, 1500000));
+    }
+
+    #[test]
+    fn test_read_zram_without_devices() {
+        let dir = tempdir().unwrap();
+
+        assert_eq!(read_zram(dir.path()), (0, 0));
+    }
+}
@@ -1,35 +1,77 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use serde::Deserialize;
 
 use crate::{component::section::Component, configuration::device::Converter};
 
+use super::hwmon::{self, HWMON};
+
 const NAME: &str = "TEMP";
 const ICON: &str = "󰏈 ";
+const ICON_WARNING: &str = "󰸁 ";
+const ICON_CRITICAL: &str = "󱃂 ";
 const TIME: u64 = 1000;
 const ZONE: u8 = 0;
+const THERMAL: &str = "/sys/class/thermal";
+/// Warning margin below the critical limit when the hardware exposes no warning limit
+const WARNING_MARGIN: i64 = 10_000;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Unit {
+    #[default]
+    #[serde(rename = "C", alias = "c")]
+    Celsius,
+    #[serde(rename = "F", alias = "f")]
+    Fahrenheit,
+    #[serde(rename = "K", alias = "k")]
+    Kelvin,
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Temperature {
     pub time: Option<u64>,
     pub name: Option<String>,
     pub icon: Option<String>,
+    pub icon_warning: Option<String>,
+    pub icon_critical: Option<String>,
+    pub unit: Option<Unit>,
     pub zone: Option<u8>,
+    pub zone_type: Option<String>,
+    pub chip: Option<String>,
+    pub label: Option<String>,
 }
 
-impl Converter for Temperature {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let path = PathBuf::from(format!(
-            "/sys/class/thermal/thermal_zone{}/temp",
-            self.zone.unwrap_or(ZONE)
-        ));
+/// Temperature of a sensor and the limits reported by the hardware, in millidegrees Celsius
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Reading {
+    millidegrees: i64,
+    warning: Option<i64>,
+    critical: Option<i64>,
+}
 
-        let contents = fs::read_to_string(path).map_err(anyhow::Error::msg)?;
-        let temp_milli: i64 = contents.trim().parse().map_err(anyhow::Error::msg)?;
+impl Reading {
+    fn is_critical(&self) -> bool {
+        self.critical
+            .is_some_and(|critical| self.millidegrees >= critical)
+    }
 
-        let total = format!("{:.0}°C", temp_milli as f32 / 1000.0);
+    fn is_warning(&self) -> bool {
+        self.warning
+            .or(self.critical.map(|critical| critical - WARNING_MARGIN))
+            .is_some_and(|warning| self.millidegrees >= warning)
+    }
+}
+
+impl Converter for Temperature {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let reading = self.read_temperature(Path::new(HWMON), Path::new(THERMAL))?;
+
+        let total = format_temperature(reading.millidegrees, self.unit.unwrap_or_default());
         let name = self.name.as_deref().unwrap_or(NAME);
-        let icon = self.icon.as_deref().unwrap_or(ICON);
+        let icon = self.icon(&reading);
 
         Ok(Component {
             name,
@@ -43,20 +85,141 @@ impl Converter for Temperature {
     }
 }
 
+impl Temperature {
+    fn icon(&self, reading: &Reading) -> &str {
+        if reading.is_critical() {
+            self.icon_critical.as_deref().unwrap_or(ICON_CRITICAL)
+        } else if reading.is_warning() {
+            self.icon_warning.as_deref().unwrap_or(ICON_WARNING)
+        } else {
+            self.icon.as_deref().unwrap_or(ICON)
+        }
+    }
+
+    /// Hottest of the selected sensors. hwmon sensors are selected by chip
+    /// and label, thermal zones by type or index
+    fn read_temperature(&self, hwmon: &Path, thermal: &Path) -> anyhow::Result<Reading> {
+        if self.chip.is_some() || self.label.is_some() {
+            let chip = self.chip.as_deref();
+            let label = self.label.as_deref();
+            return hwmon::sensors(hwmon, "temp", chip, label)
+                .iter()
+                .filter_map(|sensor| {
+                    Some(Reading {
+                        millidegrees: sensor.input()?,
+                        warning: sensor.attribute("max"),
+                        critical: sensor.attribute("crit"),
+                    })
+                })
+                .max_by_key(|reading| reading.millidegrees)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No hwmon temperature sensor found for chip {} and label {}",
+                        chip.unwrap_or("*"),
+                        label.unwrap_or("*")
+                    )
+                });
+        }
+
+        if let Some(zone_type) = &self.zone_type {
+            return thermal_zones(thermal, zone_type)
+                .iter()
+                .filter_map(|zone| read_zone(zone).ok())
+                .max_by_key(|reading| reading.millidegrees)
+                .ok_or_else(|| anyhow::anyhow!("No thermal zone found with type {zone_type}"));
+        }
+
+        read_zone(&thermal.join(format!("thermal_zone{}", self.zone.unwrap_or(ZONE))))
+    }
+}
+
 impl Default for Temperature {
     fn default() -> Self {
         Self {
             time: Some(TIME),
             name: Some(String::from(NAME)),
             icon: Some(String::from(ICON)),
+            icon_warning: Some(String::from(ICON_WARNING)),
+            icon_critical: Some(String::from(ICON_CRITICAL)),
+            unit: Some(Unit::Celsius),
             zone: Some(0),
+            zone_type: None,
+            chip: None,
+            label: None,
+        }
+    }
+}
+
+/// Thermal zone directories whose `type` matches
+fn thermal_zones(thermal: &Path, zone_type: &str) -> Vec<PathBuf> {
+    let mut zones: Vec<_> = fs::read_dir(thermal)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|zone| fs::read_to_string(zone.join("type")).is_ok_and(|t| t.trim() == zone_type))
+        .collect();
+    zones.sort();
+    zones
+}
+
+/// Temperature of a thermal zone. The `critical` trip point is its critical
+/// limit and the lowest `hot` or `passive` one its warning limit
+fn read_zone(zone: &Path) -> anyhow::Result<Reading> {
+    let mut reading = Reading {
+        millidegrees: read_millidegrees(&zone.join("temp"))?,
+        ..Default::default()
+    };
+
+    for index in 0.. {
+        let Ok(trip_type) = fs::read_to_string(zone.join(format!("trip_point_{index}_type")))
+        else {
+            break;
+        };
+        let Ok(temp) = read_millidegrees(&zone.join(format!("trip_point_{index}_temp"))) else {
+            continue;
+        };
+        // Disabled trip points report a zero or negative temperature
+        if temp <= 0 {
+            continue;
+        }
+        match trip_type.trim() {
+            "critical" => reading.critical = Some(temp),
+            "hot" | "passive" => {
+                reading.warning = Some(reading.warning.map_or(temp, |w| w.min(temp)))
+            }
+            _ => {}
         }
     }
+
+    Ok(reading)
+}
+
+fn read_millidegrees(path: &Path) -> anyhow::Result<i64> {
+    let contents = fs::read_to_string(path).map_err(anyhow::Error::msg)?;
+    contents.trim().parse().map_err(anyhow::Error::msg)
+}
+
+fn format_temperature(millidegrees: i64, unit: Unit) -> String {
+    let celsius = millidegrees as f64 / 1000.0;
+    match unit {
+        Unit::Celsius => format!("{celsius:.0}°C"),
+        Unit::Fahrenheit => format!("{:.0}°F", celsius * 9.0 / 5.0 + 32.0),
+        Unit::Kelvin => format!("{:.0}K", celsius + 273.15),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
+
+    fn create_zone(thermal: &Path, zone: &str, zone_type: &str, temp: &str) {
+        let zone = thermal.join(zone);
+        fs::create_dir_all(&zone).unwrap();
+        fs::write(zone.join("type"), format!("{zone_type}\n")).unwrap();
+        fs::write(zone.join("temp"), format!("{temp}\n")).unwrap();
+    }
 
     #[test]
     fn test_temperature_get_time() {
@@ -64,7 +227,13 @@ mod tests {
             time: Some(2000),
             name: None,
             icon: None,
+            icon_warning: None,
+            icon_critical: None,
+            unit: None,
             zone: None,
+            zone_type: None,
+            chip: None,
+            label: None,
         };
         assert_eq!(temperature.time(), 2000);
 
@@ -74,11 +243,12 @@ mod tests {
 
     #[test]
     fn test_temperature_convert() {
-        let temperature = Temperature {
+        let mut temperature = Temperature {
             time: Some(1000),
             name: Some(String::from("Current Temperature")),
             icon: Some(String::from(ICON)),
             zone: Some(2),
+            ..Default::default()
         };
 
         let component = temperature.convert().unwrap();
@@ -86,4 +256,173 @@ mod tests {
         assert_eq!(component.name, "Current Temperature");
         assert_eq!(component.icon, ICON);
     }
+
+    #[test]
+    fn test_read_temperature_from_hwmon() {
+        let hwmon = tempdir().unwrap();
+        let thermal = tempdir().unwrap();
+        hwmon::create_chip(
+            hwmon.path(),
+            "hwmon2",
+            "coretemp",
+            &[
+                ("temp1_input", "61000"),
+                ("temp1_label", "Package id 0"),
+                ("temp2_input", "58000"),
+                ("temp2_label", "Core 0"),
+                ("temp3_input", "64000"),
+                ("temp3_label", "Core 1"),
+            ],
+        );
+
+        let package = Temperature {
+            chip: Some(String::from("coretemp")),
+            label: Some(String::from("Package id 0")),
+            ..Default::default()
+        };
+        assert_eq!(
+            package
+                .read_temperature(hwmon.path(), thermal.path())
+                .unwrap()
+                .millidegrees,
+            61000
+        );
+
+        let hottest = Temperature {
+            chip: Some(String::from("coretemp")),
+            ..Default::default()
+        };
+        assert_eq!(
+            hottest
+                .read_temperature(hwmon.path(), thermal.path())
+                .unwrap()
+                .millidegrees,
+            64000
+        );
+
+        let missing = Temperature {
+            chip: Some(String::from("k10temp")),
+            ..Default::default()
+        };
+        assert!(
+            missing
+                .read_temperature(hwmon.path(), thermal.path())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_read_temperature_from_thermal_zones() {
+        let hwmon = tempdir().unwrap();
+        let thermal = tempdir().unwrap();
+        create_zone(thermal.path(), "thermal_zone0", "acpitz", "45000");
+        create_zone(thermal.path(), "thermal_zone1", "x86_pkg_temp", "52000");
+        create_zone(thermal.path(), "thermal_zone2", "x86_pkg_temp", "57000");
+
+        let by_type = Temperature {
+            zone_type: Some(String::from("x86_pkg_temp")),
+            ..Default::default()
+        };
+        assert_eq!(
+            by_type
+                .read_temperature(hwmon.path(), thermal.path())
+                .unwrap()
+                .millidegrees,
+            57000
+        );
+
+        let by_index = Temperature::default();
+        assert_eq!(
+            by_index
+                .read_temperature(hwmon.path(), thermal.path())
+                .unwrap()
+                .millidegrees,
+            45000
+        );
+    }
+
+    #[test]
+    fn test_read_zone_trip_points() {
+        let thermal = tempdir().unwrap();
+        create_zone(thermal.path(), "thermal_zone0", "acpitz", "95000");
+        let zone = thermal.path().join("thermal_zone0");
+        for (index, trip_type, temp) in [
+            (0, "critical", "105000"),
+            (1, "hot", "0"),
+            (2, "passive", "90000"),
+            (3, "active", "60000"),
+        ] {
+            fs::write(zone.join(format!("trip_point_{index}_type")), trip_type).unwrap();
+            fs::write(zone.join(format!("trip_point_{index}_temp")), temp).unwrap();
+        }
+
+        assert_eq!(
+            read_zone(&zone).unwrap(),
+            Reading {
+                millidegrees: 95000,
+                warning: Some(90000),
+                critical: Some(105000),
+            }
+        );
+    }
+
+    #[test]
+    fn test_icon_from_hwmon_limits() {
+        let hwmon = tempdir().unwrap();
+        let thermal = tempdir().unwrap();
+        hwmon::create_chip(
+            hwmon.path(),
+            "hwmon0",
+            "coretemp",
+            &[
+                ("temp1_input", "85000"),
+                ("temp1_label", "Package id 0"),
+                ("temp1_max", "80000"),
+                ("temp1_crit", "100000"),
+            ],
+        );
+        let temperature = Temperature {
+            chip: Some(String::from("coretemp")),
+            ..Default::default()
+        };
+
+        let reading = temperature
+            .read_temperature(hwmon.path(), thermal.path())
+            .unwrap();
+
+        assert_eq!(temperature.icon(&reading), ICON_WARNING);
+        assert_eq!(
+            temperature.icon(&Reading {
+                millidegrees: 100000,
+                ..reading
+            }),
+            ICON_CRITICAL
+        );
+        assert_eq!(
+            temperature.icon(&Reading {
+                millidegrees: 50000,
+                ..reading
+            }),
+            ICON
+        );
+    }
+
+    #[test]
+    fn test_warning_below_critical_without_warning_limit() {
+        let reading = Reading {
+            millidegrees: 92000,
+            warning: None,
+            critical: Some(100000),
+        };
+        assert!(reading.is_warning());
+        assert!(!reading.is_critical());
+        assert!(!Reading::default().is_warning());
+    }
+
+    #[test]
+    fn test_format_temperature() {
+        assert_eq!(format_temperature(45000, Unit::Celsius), "45°C");
+        assert_eq!(format_temperature(45000, Unit::Fahrenheit), "113°F");
+        assert_eq!(format_temperature(45000, Unit::Kelvin), "318K");
+    }
 }
@@ -0,0 +1,415 @@
+use alsa::{
+    Mixer, PollDescriptors,
+    mixer::{Selem, SelemChannelId, SelemId},
+    poll::{Flags, poll},
+};
+use serde::Deserialize;
+
+use crate::{
+    component::{placeholder, section::Component},
+    configuration::device::{Converter, Watcher},
+};
+
+use pulse::{Connection, Sink};
+
+mod pulse;
+
+const NAME: &str = "VOL";
+const ICON_ACTIVE: &str = " ";
+const ICON_MUTED: &str = "󰖁 ";
+const TIME: u64 = 100;
+const MUTED: &str = "MUTED";
+const FORMAT: &str = "{volume}";
+const CARD: &str = "default";
+const CHANNEL: &str = "front_left";
+const AVERAGE: &str = "average";
+const CONTROLS: [&str; 5] = ["Master", "PCM", "Speaker", "Headphone", "Digital"];
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Alsa,
+    Pulse,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Volume {
+    pub time: Option<u64>,
+    pub name: Option<String>,
+    pub icon_active: Option<String>,
+    pub icon_muted: Option<String>,
+    pub format: Option<String>,
+    pub backend: Option<Backend>,
+    pub card: Option<String>,
+    pub control: Option<String>,
+    pub index: Option<u32>,
+    pub channel: Option<String>,
+    pub server: Option<String>,
+}
+
+impl Converter for Volume {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        let sink = match self.backend.unwrap_or_default() {
+            Backend::Alsa => self.read(&self.open_mixer()?)?,
+            Backend::Pulse => Connection::connect(self.server.as_deref())?.default_sink()?,
+        };
+        Ok(self.component(&sink))
+    }
+
+    fn time(&self) -> u64 {
+        self.time.unwrap_or(TIME)
+    }
+}
+
+impl Watcher for Volume {
+    fn watch(&mut self, notify: &mut dyn FnMut(Component<'_>)) -> anyhow::Result<()> {
+        let mut last = String::new();
+        let mut notify_changes = |component: Component<'_>| {
+            let current = component.to_string();
+            if current != last {
+                last = current;
+                notify(component);
+            }
+        };
+
+        match self.backend.unwrap_or_default() {
+            Backend::Alsa => {
+                let mixer = self.open_mixer()?;
+                loop {
+                    notify_changes(self.component(&self.read(&mixer)?));
+
+                    let mut fds = mixer.get().map_err(anyhow::Error::msg)?;
+                    poll(&mut fds, -1).map_err(anyhow::Error::msg)?;
+                    let events = mixer.revents(&fds).map_err(anyhow::Error::msg)?;
+                    if events.intersects(Flags::ERR | Flags::HUP | Flags::NVAL) {
+                        anyhow::bail!("Mixer disconnected");
+                    }
+                    mixer.handle_events().map_err(anyhow::Error::msg)?;
+                }
+            }
+            Backend::Pulse => {
+                let mut connection = Connection::connect(self.server.as_deref())?;
+                connection.subscribe()?;
+                loop {
+                    notify_changes(self.component(&connection.default_sink()?));
+                    connection.wait_event()?;
+                }
+            }
+        }
+    }
+}
+
+impl Volume {
+    fn component(&self, sink: &Sink) -> Component<'_> {
+        let icon = if sink.muted {
+            self.icon_muted.as_deref().unwrap_or(ICON_MUTED)
+        } else {
+            self.icon_active.as_deref().unwrap_or(ICON_ACTIVE)
+        };
+
+        let volume = if sink.muted {
+            String::from(MUTED)
+        } else {
+            format!("{:.0}%", sink.volume)
+        };
+
+        let value = placeholder::fill(
+            self.format.as_deref().unwrap_or(FORMAT),
+            &[("volume", &volume), ("description", &sink.description)],
+        );
+        let name = self.name.as_deref().unwrap_or(NAME);
+
+        Component { name, icon, value }
+    }
+
+    fn open_mixer(&self) -> anyhow::Result<Mixer> {
+        open_mixer(self.card.as_deref().unwrap_or(CARD))
+    }
+
+    fn read(&self, mixer: &Mixer) -> anyhow::Result<Sink> {
+        let elem = find_selem(
+            mixer,
+            self.control.as_deref(),
+            self.index.unwrap_or(0),
+            &CONTROLS,
+            Selem::has_playback_volume,
+        )?;
+        let channels = self.channels(&elem)?;
+
+        let muted = if elem.has_playback_switch() {
+            let switches = channels
+                .iter()
+                .map(|channel| elem.get_playback_switch(*channel))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::msg)?;
+            switches.iter().all(|switch| *switch == 0)
+        } else {
+            false
+        };
+
+        let (min, max) = elem.get_playback_volume_range();
+        let volumes = channels
+            .iter()
+            .map(|channel| elem.get_playback_volume(*channel))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(anyhow::Error::msg)?;
+        let volume = volumes.iter().sum::<i64>() as f64 / volumes.len() as f64;
+
+        let volume = if max > min {
+            ((volume - min as f64) / (max - min) as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        let description = elem
+            .get_id()
+            .get_name()
+            .map(String::from)
+            .unwrap_or_default();
+
+        Ok(Sink {
+            description,
+            volume,
+            muted,
+        })
+    }
+
+    fn channels(&self, elem: &Selem) -> anyhow::Result<Vec<SelemChannelId>> {
+        let channel = self.channel.as_deref().unwrap_or(CHANNEL);
+
+        if elem.is_playback_mono() {
+            return Ok(vec![SelemChannelId::mono()]);
+        }
+
+        if channel == AVERAGE {
+            let channels: Vec<SelemChannelId> = SelemChannelId::all()
+                .iter()
+                .copied()
+                .filter(|c| *c != SelemChannelId::Unknown && *c != SelemChannelId::Last)
+                .filter(|c| elem.has_playback_channel(*c))
+                .collect();
+
+            if channels.is_empty() {
+                anyhow::bail!("Mixer control has no playback channels");
+            }
+            return Ok(channels);
+        }
+
+        let channel = parse_channel(channel)?;
+        if !elem.has_playback_channel(channel) {
+            anyhow::bail!("Mixer control has no playback channel {channel}");
+        }
+        Ok(vec![channel])
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self {
+            time: Some(TIME),
+            name: Some(String::from(NAME)),
+            icon_active: Some(String::from(ICON_ACTIVE)),
+            icon_muted: Some(String::from(ICON_MUTED)),
+            format: Some(String::from(FORMAT)),
+            backend: Some(Backend::Alsa),
+            card: Some(String::from(CARD)),
+            control: None,
+            index: Some(0),
+            channel: Some(String::from(CHANNEL)),
+            server: None,
+        }
+    }
+}
+
+/// Finds the configured control or, when none is set, the first of the
+/// fallback control names present on the card that satisfies the predicate
+pub(crate) fn find_selem<'a>(
+    mixer: &'a Mixer,
+    control: Option<&str>,
+    index: u32,
+    fallback: &[&str],
+    predicate: impl Fn(&Selem<'a>) -> bool,
+) -> anyhow::Result<Selem<'a>> {
+    if let Some(control) = control {
+        return mixer
+            .find_selem(&SelemId::new(control, index))
+            .ok_or_else(|| anyhow::anyhow!("Mixer control {control},{index} not found"));
+    }
+
+    fallback
+        .iter()
+        .filter_map(|control| mixer.find_selem(&SelemId::new(control, index)))
+        .find(|selem| predicate(selem))
+        .or_else(|| {
+            mixer
+                .iter()
+                .filter_map(Selem::new)
+                .find(|selem| predicate(selem))
+        })
+        .ok_or_else(|| anyhow::anyhow!("No suitable mixer control found"))
+}
+
+pub(crate) fn open_mixer(card: &str) -> anyhow::Result<Mixer> {
+    Mixer::new(card, false).map_err(|e| anyhow::anyhow!("Cannot open mixer of card {card}: {e}"))
+}
+
+fn parse_channel(channel: &str) -> anyhow::Result<SelemChannelId> {
+    match channel {
+        "front_left" | "mono" => Ok(SelemChannelId::FrontLeft),
+        "front_right" => Ok(SelemChannelId::FrontRight),
+        "rear_left" => Ok(SelemChannelId::RearLeft),
+        "rear_right" => Ok(SelemChannelId::RearRight),
+        "front_center" => Ok(SelemChannelId::FrontCenter),
+        "woofer" => Ok(SelemChannelId::Woofer),
+        "side_left" => Ok(SelemChannelId::SideLeft),
+        "side_right" => Ok(SelemChannelId::SideRight),
+        "rear_center" => Ok(SelemChannelId::RearCenter),
+        _ => anyhow::bail!("Invalid mixer channel {channel}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pulse::server::{FakeSink, spawn};
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_volume_get_time() {
+        let volume = Volume {
+            time: Some(200),
+            name: None,
+            icon_active: None,
+            icon_muted: None,
+            format: None,
+            backend: None,
+            card: None,
+            control: None,
+            index: None,
+            channel: None,
+            server: None,
+        };
+        assert_eq!(volume.time(), 200);
+
+        let volume_default = Volume::default();
+        assert_eq!(volume_default.time(), TIME);
+    }
+
+    #[test]
+    fn test_volume_convert() {
+        let mut volume = Volume::default();
+        let component = volume.convert().unwrap();
+
+        assert_eq!(component.name, NAME);
+        assert!(component.icon == ICON_ACTIVE || component.icon == ICON_MUTED);
+        assert!(component.value.ends_with("%") || component.value == MUTED);
+    }
+
+    #[test]
+    fn test_volume_convert_with_invalid_card() {
+        let mut volume = Volume {
+            card: Some(String::from("hw:99")),
+            ..Default::default()
+        };
+
+        assert!(volume.convert().is_err());
+    }
+
+    #[test]
+    fn test_volume_watch_with_invalid_card() {
+        let mut volume = Volume {
+            card: Some(String::from("hw:99")),
+            ..Default::default()
+        };
+
+        assert!(volume.watch(&mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_volume_convert_with_pulse_backend() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("native");
+        let server = spawn(
+            &path,
+            vec![FakeSink {
+                name: "speakers",
+                description: "Built-in Speakers",
+                volumes: vec![0xC000, 0xC000],
+                muted: false,
+            }],
+        );
+
+        let mut volume = Volume {
+            backend: Some(Backend::Pulse),
+            server: Some(format!("unix:{}", path.display())),
+            format: Some(String::from("{volume} {description}")),
+            ..Default::default()
+        };
+        let component = volume.convert().unwrap();
+
+        assert_eq!(component.icon, ICON_ACTIVE);
+        assert_eq!(component.value, "75% Built-in Speakers");
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_volume_watch_follows_default_sink() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("native");
+        let server = spawn(
+            &path,
+            vec![
+                FakeSink {
+                    name: "speakers",
+                    description: "Built-in Speakers",
+                    volumes: vec![0x10000],
+                    muted: false,
+                },
+                FakeSink {
+                    name: "headset",
+                    description: "USB Headset",
+                    volumes: vec![0x8000],
+                    muted: true,
+                },
+            ],
+        );
+
+        let mut volume = Volume {
+            backend: Some(Backend::Pulse),
+            server: Some(path.to_string_lossy().to_string()),
+            format: Some(String::from("{volume} {description}")),
+            ..Default::default()
+        };
+
+        let mut values = Vec::new();
+        let result = volume.watch(&mut |component| {
+            values.push((component.icon.to_string(), component.value));
+        });
+
+        assert!(result.is_err());
+        assert_eq!(
+            values,
+            vec![
+                (
+                    ICON_ACTIVE.to_string(),
+                    String::from("100% Built-in Speakers")
+                ),
+                (ICON_MUTED.to_string(), String::from("MUTED USB Headset")),
+            ]
+        );
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_parse_channel() {
+        assert_eq!(
+            parse_channel("front_right").unwrap(),
+            SelemChannelId::FrontRight
+        );
+        assert_eq!(parse_channel("mono").unwrap(), SelemChannelId::FrontLeft);
+        assert!(parse_channel("invalid").is_err());
+    }
+}
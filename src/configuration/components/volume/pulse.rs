@@ -0,0 +1,548 @@
+//! Minimal client of the PulseAudio native protocol, also served by pipewire-pulse.
+//! It only knows how to read the default sink and wait for its changes.
+
+use std::{
+    env, fs,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+const PROTOCOL_VERSION: u32 = 32;
+const COOKIE_LENGTH: usize = 256;
+const NO_TAG: u32 = u32::MAX;
+const NO_INDEX: u32 = u32::MAX;
+const VOLUME_NORM: f64 = 0x10000 as f64;
+
+const COMMAND_ERROR: u32 = 0;
+const COMMAND_REPLY: u32 = 2;
+const COMMAND_AUTH: u32 = 8;
+const COMMAND_SET_CLIENT_NAME: u32 = 9;
+const COMMAND_GET_SERVER_INFO: u32 = 20;
+const COMMAND_GET_SINK_INFO: u32 = 21;
+const COMMAND_SUBSCRIBE: u32 = 35;
+const COMMAND_SUBSCRIBE_EVENT: u32 = 66;
+
+const SUBSCRIPTION_MASK_SINK: u32 = 0x0001;
+const SUBSCRIPTION_MASK_SERVER: u32 = 0x0080;
+
+const TAG_STRING: u8 = b't';
+const TAG_STRING_NULL: u8 = b'N';
+const TAG_U32: u8 = b'L';
+const TAG_ARBITRARY: u8 = b'x';
+const TAG_BOOLEAN_TRUE: u8 = b'1';
+const TAG_BOOLEAN_FALSE: u8 = b'0';
+const TAG_SAMPLE_SPEC: u8 = b'a';
+const TAG_CHANNEL_MAP: u8 = b'm';
+const TAG_CVOLUME: u8 = b'v';
+const TAG_PROPLIST: u8 = b'P';
+
+#[derive(Debug, PartialEq)]
+pub struct Sink {
+    pub description: String,
+    pub volume: f64,
+    pub muted: bool,
+}
+
+pub struct Connection {
+    stream: UnixStream,
+    tag: u32,
+    pending_event: bool,
+}
+
+impl Connection {
+    pub fn connect(server: Option<&str>) -> anyhow::Result<Self> {
+        let path = socket_path(server);
+        let stream = UnixStream::connect(&path)
+            .map_err(|e| anyhow::anyhow!("Cannot connect to {}: {e}", path.display()))?;
+
+        let mut connection = Self {
+            stream,
+            tag: 0,
+            pending_event: false,
+        };
+
+        connection.request(COMMAND_AUTH, |t| {
+            t.put_u32(PROTOCOL_VERSION);
+            t.put_arbitrary(&cookie());
+        })?;
+        connection.request(COMMAND_SET_CLIENT_NAME, |t| {
+            t.put_proplist(&[("application.name", env!("CARGO_PKG_NAME"))]);
+        })?;
+
+        Ok(connection)
+    }
+
+    pub fn subscribe(&mut self) -> anyhow::Result<()> {
+        self.request(COMMAND_SUBSCRIBE, |t| {
+            t.put_u32(SUBSCRIPTION_MASK_SINK | SUBSCRIPTION_MASK_SERVER);
+        })?;
+        Ok(())
+    }
+
+    pub fn default_sink(&mut self) -> anyhow::Result<Sink> {
+        let mut info = self.request(COMMAND_GET_SERVER_INFO, |_| {})?;
+        for _ in 0..4 {
+            info.get_string()?; // package name, package version, user name, host name
+        }
+        info.get_sample_spec()?;
+        let sink_name = info.get_string()?.unwrap_or_default();
+
+        let mut info = self.request(COMMAND_GET_SINK_INFO, |t| {
+            t.put_u32(NO_INDEX);
+            t.put_string(&sink_name);
+        })?;
+        info.get_u32()?; // index
+        let name = info.get_string()?.unwrap_or_default();
+        let description = info.get_string()?.unwrap_or(name);
+        info.get_sample_spec()?;
+        info.get_channel_map()?;
+        info.get_u32()?; // owner module
+        let volumes = info.get_cvolume()?;
+        let muted = info.get_bool()?;
+
+        let volume = if volumes.is_empty() {
+            0.0
+        } else {
+            volumes.iter().map(|v| *v as f64).sum::<f64>() / volumes.len() as f64 / VOLUME_NORM
+                * 100.0
+        };
+
+        Ok(Sink {
+            description,
+            volume,
+            muted,
+        })
+    }
+
+    /// Blocks until the server reports a change on sinks or on its defaults
+    pub fn wait_event(&mut self) -> anyhow::Result<()> {
+        while !self.pending_event {
+            let (command, _, _) = self.read_packet()?;
+            if command == COMMAND_SUBSCRIBE_EVENT {
+                self.pending_event = true;
+            }
+        }
+        self.pending_event = false;
+        Ok(())
+    }
+
+    fn request<F>(&mut self, command: u32, build: F) -> anyhow::Result<TagReader>
+    where
+        F: FnOnce(&mut TagWriter),
+    {
+        let tag = self.tag;
+        self.tag = self.tag.wrapping_add(1);
+
+        let mut writer = TagWriter::default();
+        writer.put_u32(command);
+        writer.put_u32(tag);
+        build(&mut writer);
+        write_packet(&mut self.stream, &writer.0)?;
+
+        loop {
+            let (command, reply_tag, reader) = self.read_packet()?;
+            match command {
+                COMMAND_SUBSCRIBE_EVENT => self.pending_event = true,
+                COMMAND_REPLY if reply_tag == tag => return Ok(reader),
+                COMMAND_ERROR if reply_tag == tag => {
+                    let mut reader = reader;
+                    anyhow::bail!("PulseAudio error code {}", reader.get_u32().unwrap_or(0))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_packet(&mut self) -> anyhow::Result<(u32, u32, TagReader)> {
+        loop {
+            let (channel, payload) = read_packet(&mut self.stream)?;
+            if channel != NO_TAG {
+                continue;
+            }
+            let mut reader = TagReader::new(payload);
+            let command = reader.get_u32()?;
+            let tag = reader.get_u32()?;
+            return Ok((command, tag, reader));
+        }
+    }
+}
+
+fn socket_path(server: Option<&str>) -> PathBuf {
+    let server = server
+        .map(String::from)
+        .or_else(|| env::var("PULSE_SERVER").ok());
+
+    if let Some(server) = server {
+        return PathBuf::from(server.trim_start_matches("unix:"));
+    }
+
+    let runtime_dir = env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| format!("/run/user/{}", unsafe { libc::getuid() }));
+
+    PathBuf::from(runtime_dir).join("pulse/native")
+}
+
+fn cookie() -> Vec<u8> {
+    let path = env::var("PULSE_COOKIE")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| PathBuf::from(home).join(".config/pulse/cookie")));
+
+    path.ok()
+        .and_then(|path| fs::read(path).ok())
+        .filter(|cookie| cookie.len() == COOKIE_LENGTH)
+        .unwrap_or_else(|| vec![0; COOKIE_LENGTH])
+}
+
+fn write_packet(stream: &mut impl Write, payload: &[u8]) -> anyhow::Result<()> {
+    let mut packet = Vec::with_capacity(20 + payload.len());
+    packet.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    packet.extend_from_slice(&NO_TAG.to_be_bytes());
+    packet.extend_from_slice(&[0; 12]); // offset and flags
+    packet.extend_from_slice(payload);
+    stream.write_all(&packet).map_err(anyhow::Error::msg)
+}
+
+fn read_packet(stream: &mut impl Read) -> anyhow::Result<(u32, Vec<u8>)> {
+    let mut header = [0; 20];
+    stream.read_exact(&mut header).map_err(anyhow::Error::msg)?;
+
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let channel = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+
+    let mut payload = vec![0; length as usize];
+    stream
+        .read_exact(&mut payload)
+        .map_err(anyhow::Error::msg)?;
+    Ok((channel, payload))
+}
+
+#[derive(Default)]
+struct TagWriter(Vec<u8>);
+
+impl TagWriter {
+    fn put_u32(&mut self, value: u32) {
+        self.0.push(TAG_U32);
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_string(&mut self, value: &str) {
+        self.0.push(TAG_STRING);
+        self.0.extend_from_slice(value.as_bytes());
+        self.0.push(0);
+    }
+
+    fn put_arbitrary(&mut self, value: &[u8]) {
+        self.0.push(TAG_ARBITRARY);
+        self.0
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.0.extend_from_slice(value);
+    }
+
+    fn put_proplist(&mut self, properties: &[(&str, &str)]) {
+        self.0.push(TAG_PROPLIST);
+        for (key, value) in properties {
+            let mut value = value.as_bytes().to_vec();
+            value.push(0);
+            self.put_string(key);
+            self.put_u32(value.len() as u32);
+            self.put_arbitrary(&value);
+        }
+        self.0.push(TAG_STRING_NULL);
+    }
+
+    #[cfg(test)]
+    fn put_bool(&mut self, value: bool) {
+        self.0.push(if value {
+            TAG_BOOLEAN_TRUE
+        } else {
+            TAG_BOOLEAN_FALSE
+        });
+    }
+
+    #[cfg(test)]
+    fn put_sample_spec(&mut self, channels: u8) {
+        self.0.extend_from_slice(&[TAG_SAMPLE_SPEC, 3, channels]);
+        self.0.extend_from_slice(&44100u32.to_be_bytes());
+    }
+
+    #[cfg(test)]
+    fn put_channel_map(&mut self, channels: u8) {
+        self.0.extend_from_slice(&[TAG_CHANNEL_MAP, channels]);
+        self.0.extend((1..=channels).collect::<Vec<u8>>());
+    }
+
+    #[cfg(test)]
+    fn put_cvolume(&mut self, volumes: &[u32]) {
+        self.0
+            .extend_from_slice(&[TAG_CVOLUME, volumes.len() as u8]);
+        for volume in volumes {
+            self.0.extend_from_slice(&volume.to_be_bytes());
+        }
+    }
+}
+
+struct TagReader {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl TagReader {
+    fn new(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> anyhow::Result<&[u8]> {
+        let end = self.position + length;
+        if end > self.data.len() {
+            anyhow::bail!("Truncated PulseAudio packet");
+        }
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn expect(&mut self, tag: u8) -> anyhow::Result<()> {
+        let found = self.take(1)?[0];
+        if found != tag {
+            anyhow::bail!(
+                "Unexpected PulseAudio tag {:?}, expected {:?}",
+                found as char,
+                tag as char
+            );
+        }
+        Ok(())
+    }
+
+    fn get_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn get_u32(&mut self) -> anyhow::Result<u32> {
+        self.expect(TAG_U32)?;
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn get_string(&mut self) -> anyhow::Result<Option<String>> {
+        match self.get_u8()? {
+            TAG_STRING_NULL => Ok(None),
+            TAG_STRING => {
+                let rest = &self.data[self.position..];
+                let end = rest
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or_else(|| anyhow::anyhow!("Unterminated PulseAudio string"))?;
+                let value = String::from_utf8_lossy(&rest[..end]).to_string();
+                self.position += end + 1;
+                Ok(Some(value))
+            }
+            tag => anyhow::bail!(
+                "Unexpected PulseAudio tag {:?}, expected string",
+                tag as char
+            ),
+        }
+    }
+
+    fn get_bool(&mut self) -> anyhow::Result<bool> {
+        match self.get_u8()? {
+            TAG_BOOLEAN_TRUE => Ok(true),
+            TAG_BOOLEAN_FALSE => Ok(false),
+            tag => anyhow::bail!("Unexpected PulseAudio tag {:?}, expected bool", tag as char),
+        }
+    }
+
+    fn get_sample_spec(&mut self) -> anyhow::Result<()> {
+        self.expect(TAG_SAMPLE_SPEC)?;
+        self.take(6)?; // format, channels and rate
+        Ok(())
+    }
+
+    fn get_channel_map(&mut self) -> anyhow::Result<()> {
+        self.expect(TAG_CHANNEL_MAP)?;
+        let channels = self.get_u8()? as usize;
+        self.take(channels)?;
+        Ok(())
+    }
+
+    fn get_cvolume(&mut self) -> anyhow::Result<Vec<u32>> {
+        self.expect(TAG_CVOLUME)?;
+        let channels = self.get_u8()? as usize;
+        let bytes = self.take(channels * 4)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+}
+
+/// Stand-in PulseAudio server answering the requests made by [`Connection`]
+#[cfg(test)]
+pub mod server {
+    use super::*;
+    use std::{os::unix::net::UnixListener, path::Path, thread};
+
+    pub struct FakeSink {
+        pub name: &'static str,
+        pub description: &'static str,
+        pub volumes: Vec<u32>,
+        pub muted: bool,
+    }
+
+    /// Serves one client. Once a subscribed client has read the default sink,
+    /// the next one becomes the default and a server change event is sent.
+    /// The connection is closed after the last sink has been read
+    pub fn spawn(path: &Path, sinks: Vec<FakeSink>) -> thread::JoinHandle<()> {
+        let listener = UnixListener::bind(path).unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut current = 0;
+            let mut subscribed = false;
+
+            while let Ok((_, payload)) = read_packet(&mut stream) {
+                let mut request = TagReader::new(payload);
+                let command = request.get_u32().unwrap();
+                let tag = request.get_u32().unwrap();
+
+                let mut reply = TagWriter::default();
+                reply.put_u32(COMMAND_REPLY);
+                reply.put_u32(tag);
+
+                match command {
+                    COMMAND_AUTH => reply.put_u32(PROTOCOL_VERSION),
+                    COMMAND_SET_CLIENT_NAME => reply.put_u32(0),
+                    COMMAND_GET_SERVER_INFO => {
+                        for value in ["pulseaudio", "17.0", "user", "host"] {
+                            reply.put_string(value);
+                        }
+                        reply.put_sample_spec(2);
+                        reply.put_string(sinks[current].name);
+                        reply.put_string("source");
+                    }
+                    COMMAND_GET_SINK_INFO => {
+                        request.get_u32().unwrap();
+                        let name = request.get_string().unwrap().unwrap();
+                        let Some(sink) = sinks.iter().find(|s| s.name == name) else {
+                            let mut error = TagWriter::default();
+                            error.put_u32(COMMAND_ERROR);
+                            error.put_u32(tag);
+                            error.put_u32(5);
+                            write_packet(&mut stream, &error.0).unwrap();
+                            continue;
+                        };
+                        reply.put_u32(0);
+                        reply.put_string(sink.name);
+                        reply.put_string(sink.description);
+                        reply.put_sample_spec(sink.volumes.len() as u8);
+                        reply.put_channel_map(sink.volumes.len() as u8);
+                        reply.put_u32(0);
+                        reply.put_cvolume(&sink.volumes);
+                        reply.put_bool(sink.muted);
+                    }
+                    COMMAND_SUBSCRIBE => subscribed = true,
+                    _ => panic!("Unexpected command {command}"),
+                }
+                write_packet(&mut stream, &reply.0).unwrap();
+
+                if command == COMMAND_GET_SINK_INFO && subscribed {
+                    if current + 1 == sinks.len() {
+                        break;
+                    }
+                    current += 1;
+                    let mut event = TagWriter::default();
+                    event.put_u32(COMMAND_SUBSCRIBE_EVENT);
+                    event.put_u32(NO_TAG);
+                    event.put_u32(0x0017); // server change
+                    event.put_u32(0);
+                    write_packet(&mut stream, &event.0).unwrap();
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::server::{FakeSink, spawn};
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_default_sink() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("native");
+        let server = spawn(
+            &path,
+            vec![FakeSink {
+                name: "speakers",
+                description: "Built-in Speakers",
+                volumes: vec![0x8000, 0x8000],
+                muted: false,
+            }],
+        );
+
+        let mut connection = Connection::connect(path.to_str()).unwrap();
+        let sink = connection.default_sink().unwrap();
+
+        assert_eq!(
+            sink,
+            Sink {
+                description: String::from("Built-in Speakers"),
+                volume: 50.0,
+                muted: false,
+            }
+        );
+
+        drop(connection);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_follow_default_sink_changes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("native");
+        let server = spawn(
+            &path,
+            vec![
+                FakeSink {
+                    name: "speakers",
+                    description: "Built-in Speakers",
+                    volumes: vec![0x10000],
+                    muted: false,
+                },
+                FakeSink {
+                    name: "headset",
+                    description: "USB Headset",
+                    volumes: vec![0x4000, 0x4000],
+                    muted: true,
+                },
+            ],
+        );
+
+        let mut connection = Connection::connect(path.to_str()).unwrap();
+        connection.subscribe().unwrap();
+        assert_eq!(
+            connection.default_sink().unwrap().description,
+            "Built-in Speakers"
+        );
+
+        connection.wait_event().unwrap();
+        let sink = connection.default_sink().unwrap();
+
+        assert_eq!(sink.description, "USB Headset");
+        assert_eq!(sink.volume, 25.0);
+        assert!(sink.muted);
+
+        drop(connection);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_connect_without_server() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing");
+
+        assert!(Connection::connect(path.to_str()).is_err());
+    }
+}
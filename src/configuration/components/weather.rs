@@ -1,93 +1,879 @@
-use std::process::Command;
+use std::{thread, time::Duration};
 
+use chrono::{DateTime, NaiveTime};
 use serde::Deserialize;
 
-use crate::{component::section::Component, configuration::device::Converter};
+use crate::{
+    component::{placeholder, section::Component},
+    configuration::device::{Converter, Watcher},
+};
 
 const NAME: &str = "WEA";
 const ICON: &str = " ";
 const TIME: u64 = 1800000;
+const TIMEOUT: u64 = 10000;
+const FORMAT: &str = "{temp}";
+const WTTR_URL: &str = "https://wttr.in";
+const OPEN_METEO_URL: &str = "https://api.open-meteo.com";
+const OPENWEATHERMAP_URL: &str = "https://api.openweathermap.org";
+const API_KEY_ENV: &str = "OPENWEATHERMAP_API_KEY";
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Service {
+    /// wttr.in, by location name
+    #[default]
+    Wttr,
+    /// Open-Meteo, by latitude and longitude
+    OpenMeteo,
+    /// OpenWeatherMap, by location name or latitude and longitude. Requires an API key
+    OpenWeatherMap,
+}
+
+impl Service {
+    fn provider(&self) -> &'static dyn Provider {
+        match self {
+            Service::Wttr => &Wttr,
+            Service::OpenMeteo => &OpenMeteo,
+            Service::OpenWeatherMap => &OpenWeatherMap,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// °C and km/h
+    #[default]
+    Metric,
+    /// °F and mph
+    Imperial,
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Weather {
     pub time: Option<u64>,
     pub name: Option<String>,
+    /// Static icon. The icon of the current condition is shown when not set
     pub icon: Option<String>,
+    #[serde(default)]
     pub location: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub provider: Option<Service>,
+    /// Environment variable holding the OpenWeatherMap API key
+    pub api_key_env: Option<String>,
+    /// Base URL of the provider
+    pub url: Option<String>,
+    pub timeout: Option<u64>,
+    pub format: Option<String>,
+    pub units: Option<Units>,
+    #[serde(skip)]
+    last: Option<Conditions>,
 }
 
-impl Converter for Weather {
-    fn convert(&self) -> anyhow::Result<Component<'_>> {
-        let name = self.name.as_deref().unwrap_or(NAME);
-        let icon = self.icon.as_deref().unwrap_or(ICON);
+/// Group of weather codes sharing an icon
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sky {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Rain,
+    HeavyRain,
+    Snow,
+    Thunder,
+}
 
-        let output = Command::new("curl")
-            .arg(format!("wttr.in/{}?format=%t", self.location))
-            .output()
-            .map_err(anyhow::Error::msg)?;
+impl Sky {
+    fn icon(&self) -> &'static str {
+        match self {
+            Sky::Clear => "󰖙 ",
+            Sky::PartlyCloudy => "󰖕 ",
+            Sky::Cloudy => "󰖐 ",
+            Sky::Fog => "󰖑 ",
+            Sky::Rain => "󰖗 ",
+            Sky::HeavyRain => "󰖖 ",
+            Sky::Snow => "󰖘 ",
+            Sky::Thunder => "󰖓 ",
+        }
+    }
 
-        let temp = if output.status.success() {
-            let mut res = String::from_utf8_lossy(&output.stdout)
-                .to_string()
-                .replace("+", "");
-            res.truncate(5);
-            res
-        } else {
-            String::from("-")
+    /// World Weather Online codes used by wttr.in
+    fn from_wwo(code: u16) -> Sky {
+        match code {
+            113 => Sky::Clear,
+            116 => Sky::PartlyCloudy,
+            119 | 122 => Sky::Cloudy,
+            143 | 248 | 260 => Sky::Fog,
+            299 | 302 | 305 | 308 | 356 | 359 => Sky::HeavyRain,
+            179 | 182 | 185 | 227 | 230 | 281 | 284 | 311 | 314 | 317 | 320 | 323 | 326 | 329
+            | 332 | 335 | 338 | 350 | 362 | 365 | 368 | 371 | 374 | 377 => Sky::Snow,
+            200 | 386 | 389 | 392 | 395 => Sky::Thunder,
+            _ => Sky::Rain,
+        }
+    }
+
+    /// WMO codes used by Open-Meteo
+    fn from_wmo(code: u16) -> Sky {
+        match code {
+            0 | 1 => Sky::Clear,
+            2 => Sky::PartlyCloudy,
+            3 => Sky::Cloudy,
+            45 | 48 => Sky::Fog,
+            65 | 67 | 82 => Sky::HeavyRain,
+            71..=77 | 85 | 86 => Sky::Snow,
+            95..=99 => Sky::Thunder,
+            _ => Sky::Rain,
+        }
+    }
+
+    /// OpenWeatherMap condition ids
+    fn from_owm(id: u16) -> Sky {
+        match id {
+            200..=299 => Sky::Thunder,
+            502..=504 | 522 | 531 => Sky::HeavyRain,
+            511 | 600..=699 => Sky::Snow,
+            700..=799 => Sky::Fog,
+            800 => Sky::Clear,
+            801 | 802 => Sky::PartlyCloudy,
+            803 | 804 => Sky::Cloudy,
+            _ => Sky::Rain,
+        }
+    }
+}
+
+/// Current weather in metric units
+#[derive(Debug, Clone, PartialEq)]
+struct Conditions {
+    sky: Sky,
+    condition: String,
+    temp: f64,
+    feels_like: f64,
+    humidity: u8,
+    wind_speed: f64,
+    wind_direction: String,
+    sunrise: String,
+    sunset: String,
+}
+
+impl Conditions {
+    fn values(&self, units: Units) -> Vec<(&str, String)> {
+        let (temp, feels_like, wind) = match units {
+            Units::Metric => (
+                format!("{:.0}°C", self.temp),
+                format!("{:.0}°C", self.feels_like),
+                format!("{:.0}km/h", self.wind_speed),
+            ),
+            Units::Imperial => (
+                format!("{:.0}°F", celsius_to_fahrenheit(self.temp)),
+                format!("{:.0}°F", celsius_to_fahrenheit(self.feels_like)),
+                format!("{:.0}mph", self.wind_speed / 1.609344),
+            ),
         };
 
-        Ok(Component {
-            name,
-            icon,
-            value: temp,
+        vec![
+            ("temp", temp),
+            ("feels_like", feels_like),
+            ("condition", self.condition.clone()),
+            ("humidity", format!("{}%", self.humidity)),
+            (
+                "wind",
+                format!("{wind} {}", self.wind_direction).trim().to_string(),
+            ),
+            ("sunrise", self.sunrise.clone()),
+            ("sunset", self.sunset.clone()),
+        ]
+    }
+}
+
+/// HTTP request of a provider, relative to its base URL
+#[derive(Debug, PartialEq)]
+struct Request {
+    path: String,
+    query: Vec<(&'static str, String)>,
+}
+
+/// Source of the current weather
+trait Provider: Sync {
+    fn base_url(&self) -> &'static str;
+    fn request(&self, weather: &Weather) -> anyhow::Result<Request>;
+    fn parse(&self, body: &str) -> anyhow::Result<Conditions>;
+}
+
+struct Wttr;
+struct OpenMeteo;
+struct OpenWeatherMap;
+
+/// `format=j1` response of wttr.in. Every number comes as a string
+#[derive(Deserialize, Debug)]
+struct WttrResponse {
+    current_condition: Vec<WttrCurrent>,
+    #[serde(default)]
+    weather: Vec<WttrDay>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WttrCurrent {
+    #[serde(rename = "temp_C")]
+    temp: String,
+    #[serde(rename = "FeelsLikeC")]
+    feels_like: String,
+    humidity: String,
+    #[serde(rename = "weatherCode")]
+    code: String,
+    #[serde(rename = "weatherDesc")]
+    description: Vec<WttrValue>,
+    #[serde(rename = "windspeedKmph")]
+    wind_speed: String,
+    #[serde(rename = "winddir16Point")]
+    wind_direction: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct WttrDay {
+    astronomy: Vec<WttrAstronomy>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WttrAstronomy {
+    sunrise: String,
+    sunset: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct WttrValue {
+    value: String,
+}
+
+impl Provider for Wttr {
+    fn base_url(&self) -> &'static str {
+        WTTR_URL
+    }
+
+    fn request(&self, weather: &Weather) -> anyhow::Result<Request> {
+        let location = match (weather.latitude, weather.longitude) {
+            (Some(latitude), Some(longitude)) if weather.location.is_empty() => {
+                format!("{latitude},{longitude}")
+            }
+            _ => weather.location.clone(),
+        };
+
+        Ok(Request {
+            path: format!("/{location}"),
+            query: vec![("format", String::from("j1"))],
         })
     }
 
+    fn parse(&self, body: &str) -> anyhow::Result<Conditions> {
+        let response: WttrResponse = serde_json::from_str(body).map_err(anyhow::Error::msg)?;
+        let current = response
+            .current_condition
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No current condition in weather response"))?;
+        let astronomy = response
+            .weather
+            .first()
+            .and_then(|day| day.astronomy.first());
+        let number = |value: &str| value.trim().parse::<f64>().map_err(anyhow::Error::msg);
+        // Sunrise and sunset come as 12-hour times such as "07:48 PM"
+        let hour = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%I:%M %p")
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default()
+        };
+
+        Ok(Conditions {
+            sky: Sky::from_wwo(current.code.trim().parse().map_err(anyhow::Error::msg)?),
+            condition: current
+                .description
+                .first()
+                .map(|d| d.value.trim().to_string())
+                .unwrap_or_default(),
+            temp: number(&current.temp)?,
+            feels_like: number(&current.feels_like)?,
+            humidity: current
+                .humidity
+                .trim()
+                .parse()
+                .map_err(anyhow::Error::msg)?,
+            wind_speed: number(&current.wind_speed)?,
+            wind_direction: current.wind_direction.clone(),
+            sunrise: astronomy.map(|a| hour(&a.sunrise)).unwrap_or_default(),
+            sunset: astronomy.map(|a| hour(&a.sunset)).unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenMeteoResponse {
+    current: OpenMeteoCurrent,
+    daily: Option<OpenMeteoDaily>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenMeteoCurrent {
+    temperature_2m: f64,
+    apparent_temperature: f64,
+    relative_humidity_2m: u8,
+    weather_code: u16,
+    wind_speed_10m: f64,
+    wind_direction_10m: f64,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenMeteoDaily {
+    sunrise: Vec<String>,
+    sunset: Vec<String>,
+}
+
+impl Provider for OpenMeteo {
+    fn base_url(&self) -> &'static str {
+        OPEN_METEO_URL
+    }
+
+    fn request(&self, weather: &Weather) -> anyhow::Result<Request> {
+        let (Some(latitude), Some(longitude)) = (weather.latitude, weather.longitude) else {
+            anyhow::bail!("Open-Meteo requires latitude and longitude");
+        };
+
+        Ok(Request {
+            path: String::from("/v1/forecast"),
+            query: vec![
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                (
+                    "current",
+                    String::from(
+                        "temperature_2m,apparent_temperature,relative_humidity_2m,weather_code,wind_speed_10m,wind_direction_10m",
+                    ),
+                ),
+                ("daily", String::from("sunrise,sunset")),
+                ("timezone", String::from("auto")),
+                ("forecast_days", String::from("1")),
+            ],
+        })
+    }
+
+    fn parse(&self, body: &str) -> anyhow::Result<Conditions> {
+        let response: OpenMeteoResponse = serde_json::from_str(body).map_err(anyhow::Error::msg)?;
+        let current = response.current;
+        // Sunrise and sunset come as local ISO 8601 times such as "2024-01-01T06:12"
+        let hour = |times: Option<&Vec<String>>| {
+            times
+                .and_then(|times| times.first())
+                .and_then(|time| time.split_once('T'))
+                .map(|(_, time)| time.to_string())
+                .unwrap_or_default()
+        };
+
+        Ok(Conditions {
+            sky: Sky::from_wmo(current.weather_code),
+            condition: wmo_description(current.weather_code).to_string(),
+            temp: current.temperature_2m,
+            feels_like: current.apparent_temperature,
+            humidity: current.relative_humidity_2m,
+            wind_speed: current.wind_speed_10m,
+            wind_direction: compass(current.wind_direction_10m).to_string(),
+            sunrise: hour(response.daily.as_ref().map(|d| &d.sunrise)),
+            sunset: hour(response.daily.as_ref().map(|d| &d.sunset)),
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherMapResponse {
+    weather: Vec<OpenWeatherMapCondition>,
+    main: OpenWeatherMapMain,
+    wind: OpenWeatherMapWind,
+    sys: OpenWeatherMapSys,
+    #[serde(default)]
+    timezone: i64,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherMapCondition {
+    id: u16,
+    description: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherMapMain {
+    temp: f64,
+    feels_like: f64,
+    humidity: u8,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherMapWind {
+    speed: f64,
+    #[serde(default)]
+    deg: f64,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherMapSys {
+    sunrise: i64,
+    sunset: i64,
+}
+
+impl Provider for OpenWeatherMap {
+    fn base_url(&self) -> &'static str {
+        OPENWEATHERMAP_URL
+    }
+
+    fn request(&self, weather: &Weather) -> anyhow::Result<Request> {
+        let variable = weather.api_key_env.as_deref().unwrap_or(API_KEY_ENV);
+        let api_key = std::env::var(variable)
+            .map_err(|_| anyhow::anyhow!("OpenWeatherMap API key variable {variable} not set"))?;
+
+        let mut query = match (weather.latitude, weather.longitude) {
+            (Some(latitude), Some(longitude)) => vec![
+                ("lat", latitude.to_string()),
+                ("lon", longitude.to_string()),
+            ],
+            _ => vec![("q", weather.location.replace('+', " "))],
+        };
+        query.push(("units", String::from("metric")));
+        query.push(("appid", api_key));
+
+        Ok(Request {
+            path: String::from("/data/2.5/weather"),
+            query,
+        })
+    }
+
+    fn parse(&self, body: &str) -> anyhow::Result<Conditions> {
+        let response: OpenWeatherMapResponse =
+            serde_json::from_str(body).map_err(anyhow::Error::msg)?;
+        let condition = response
+            .weather
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No condition in weather response"))?;
+        // Sunrise and sunset come as UTC timestamps, shown in the location's time zone
+        let hour = |timestamp: i64| {
+            DateTime::from_timestamp(timestamp + response.timezone, 0)
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default()
+        };
+        let mut description = condition.description.chars();
+
+        Ok(Conditions {
+            sky: Sky::from_owm(condition.id),
+            condition: description
+                .next()
+                .map(|first| first.to_uppercase().chain(description).collect())
+                .unwrap_or_default(),
+            temp: response.main.temp,
+            feels_like: response.main.feels_like,
+            humidity: response.main.humidity,
+            wind_speed: response.wind.speed * 3.6,
+            wind_direction: compass(response.wind.deg).to_string(),
+            sunrise: hour(response.sys.sunrise),
+            sunset: hour(response.sys.sunset),
+        })
+    }
+}
+
+impl Converter for Weather {
+    fn convert(&mut self) -> anyhow::Result<Component<'_>> {
+        // Keeps showing the last fetched conditions while offline
+        match self.fetch() {
+            Ok(conditions) => self.last = Some(conditions),
+            Err(e) => eprintln!("Weather fetch error: {e}"),
+        }
+
+        let name = self.name.as_deref().unwrap_or(NAME);
+        let Some(conditions) = &self.last else {
+            let icon = self.icon.as_deref().unwrap_or(ICON);
+            return Ok(Component {
+                name,
+                icon,
+                value: String::from("-"),
+            });
+        };
+
+        let icon = self.icon.as_deref().unwrap_or(conditions.sky.icon());
+        let value = placeholder::fill(
+            self.format.as_deref().unwrap_or(FORMAT),
+            &conditions.values(self.units.unwrap_or_default()),
+        );
+
+        Ok(Component { name, icon, value })
+    }
+
     fn time(&self) -> u64 {
         self.time.unwrap_or(TIME)
     }
 }
 
+/// Fetches on its own thread, so a slow or unreachable provider does not hold
+/// back the other components refreshed every `time` miliseconds
+impl Watcher for Weather {
+    fn watch(&mut self, notify: &mut dyn FnMut(Component<'_>)) -> anyhow::Result<()> {
+        loop {
+            notify(self.convert()?);
+            thread::sleep(Duration::from_millis(self.time()));
+        }
+    }
+}
+
+impl Weather {
+    fn fetch(&self) -> anyhow::Result<Conditions> {
+        let provider = self.provider.unwrap_or_default().provider();
+        let request = provider.request(self)?;
+        let url = format!(
+            "{}{}",
+            self.url
+                .as_deref()
+                .unwrap_or(provider.base_url())
+                .trim_end_matches('/'),
+            request.path
+        );
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_millis(self.timeout.unwrap_or(TIMEOUT))))
+            .build()
+            .into();
+
+        let body = agent
+            .get(&url)
+            .query_pairs(request.query.iter().map(|(k, v)| (*k, v.as_str())))
+            .call()
+            .map_err(anyhow::Error::msg)?
+            .body_mut()
+            .read_to_string()
+            .map_err(anyhow::Error::msg)?;
+
+        provider.parse(&body)
+    }
+}
+
 impl Default for Weather {
     fn default() -> Self {
         Self {
             time: Some(TIME),
             name: Some(String::from(NAME)),
-            icon: Some(String::from(ICON)),
+            icon: None,
             location: String::from(""),
+            latitude: None,
+            longitude: None,
+            provider: Some(Service::Wttr),
+            api_key_env: None,
+            url: None,
+            timeout: Some(TIMEOUT),
+            format: Some(String::from(FORMAT)),
+            units: Some(Units::Metric),
+            last: None,
         }
     }
 }
 
+fn celsius_to_fahrenheit(celsius: f64) -> f64 {
+    celsius * 9.0 / 5.0 + 32.0
+}
+
+/// 16-point compass direction of a bearing in degrees
+fn compass(degrees: f64) -> &'static str {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    let index = (degrees.rem_euclid(360.0) / 22.5).round() as usize % POINTS.len();
+    POINTS[index]
+}
+
+fn wmo_description(code: u16) -> &'static str {
+    match code {
+        0 => "Clear sky",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51 | 53 | 55 => "Drizzle",
+        56 | 57 => "Freezing drizzle",
+        61 | 63 => "Rain",
+        65 => "Heavy rain",
+        66 | 67 => "Freezing rain",
+        71 | 73 | 75 | 77 => "Snow",
+        80..=82 => "Rain showers",
+        85 | 86 => "Snow showers",
+        95..=99 => "Thunderstorm",
+        _ => "Unknown",
+    }
+}
+
+/// Serves the given HTTP responses, one per connection, on a local port
+#[cfg(test)]
+pub fn serve(responses: Vec<(u16, String)>) -> String {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for (status, body) in responses {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status} OK\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    url
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configuration::device::Device;
+
+    const WTTR_J1: &str = r#"{
+        "current_condition": [{
+            "FeelsLikeC": "27", "FeelsLikeF": "81", "humidity": "65",
+            "temp_C": "25", "temp_F": "77", "weatherCode": "116",
+            "weatherDesc": [{"value": "Partly cloudy"}],
+            "windspeedKmph": "16", "windspeedMiles": "10", "winddir16Point": "NE"
+        }],
+        "weather": [{"astronomy": [{"sunrise": "06:12 AM", "sunset": "07:48 PM"}]}]
+    }"#;
+
+    const OPEN_METEO: &str = r#"{
+        "latitude": -34.6, "longitude": -58.38,
+        "current": {
+            "time": "2024-05-01T10:00", "temperature_2m": 18.2,
+            "apparent_temperature": 17.5, "relative_humidity_2m": 88,
+            "weather_code": 61, "wind_speed_10m": 12.3, "wind_direction_10m": 230
+        },
+        "daily": {"time": ["2024-05-01"], "sunrise": ["2024-05-01T07:02"], "sunset": ["2024-05-01T18:05"]}
+    }"#;
+
+    const OPENWEATHERMAP: &str = r#"{
+        "weather": [{"id": 803, "main": "Clouds", "description": "broken clouds"}],
+        "main": {"temp": 21.3, "feels_like": 21.6, "humidity": 60},
+        "wind": {"speed": 4.5, "deg": 50},
+        "sys": {"sunrise": 1714556400, "sunset": 1714596900},
+        "timezone": -10800
+    }"#;
 
     #[test]
     fn test_convert_success() {
-        let weather = Weather {
+        let mut weather = Weather {
             time: Some(TIME),
             name: Some(String::from(NAME)),
             icon: Some(String::from(ICON)),
             location: String::from("Buenos+Aires"),
+            latitude: None,
+            longitude: None,
+            provider: None,
+            api_key_env: None,
+            url: Some(serve(vec![(200, String::from(WTTR_J1))])),
+            timeout: None,
+            format: None,
+            units: None,
+            last: None,
         };
 
         let component = weather.convert().unwrap();
 
         assert_eq!(component.name, NAME);
         assert_eq!(component.icon, ICON);
-        assert!(!component.value.is_empty());
-        assert!(component.value.len() > 4);
+        assert_eq!(component.value, "25°C");
+    }
+
+    #[test]
+    fn test_weather_is_watched() {
+        let mut device = Device::Weather(Weather::default());
+
+        assert!(device.watcher().is_some());
     }
 
     #[test]
     fn test_convert_failure() {
-        let weather = Weather::default();
+        let mut weather = Weather {
+            url: Some(String::from("http://127.0.0.1:9")),
+            ..Default::default()
+        };
 
         let component = weather.convert().unwrap();
 
         assert_eq!(component.name, NAME);
         assert_eq!(component.icon, ICON);
-        assert!(!component.value.is_empty());
+        assert_eq!(component.value, "-");
+    }
+
+    #[test]
+    fn test_convert_keeps_last_value_when_offline() {
+        let mut weather = Weather {
+            location: String::from("Buenos+Aires"),
+            url: Some(serve(vec![
+                (200, String::from(WTTR_J1)),
+                (503, String::from("Service Unavailable")),
+                (200, String::from("Unknown location")),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(weather.convert().unwrap().value, "25°C");
+        assert_eq!(weather.convert().unwrap().value, "25°C");
+        assert_eq!(weather.convert().unwrap().value, "25°C");
+    }
+
+    #[test]
+    fn test_convert_with_placeholders_and_condition_icon() {
+        let mut weather = Weather {
+            location: String::from("Buenos+Aires"),
+            url: Some(serve(vec![(200, String::from(WTTR_J1))])),
+            format: Some(String::from(
+                "{condition} {temp} ({feels_like}) {humidity} {wind} {sunrise}-{sunset}",
+            )),
+            units: Some(Units::Imperial),
+            ..Default::default()
+        };
+
+        let component = weather.convert().unwrap();
+
+        assert_eq!(component.icon, Sky::PartlyCloudy.icon());
+        assert_eq!(
+            component.value,
+            "Partly cloudy 77°F (81°F) 65% 10mph NE 06:12-19:48"
+        );
+    }
+
+    #[test]
+    fn test_parse_wttr() {
+        let conditions = Wttr.parse(WTTR_J1).unwrap();
+
+        assert_eq!(conditions.sky, Sky::PartlyCloudy);
+        assert_eq!(conditions.temp, 25.0);
+        assert_eq!(conditions.humidity, 65);
+        assert_eq!(conditions.sunset, "19:48");
+        assert!(Wttr.parse("Unknown location").is_err());
+        assert!(Wttr.parse(r#"{"current_condition": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_wttr_request_by_coordinates() {
+        let weather = Weather {
+            latitude: Some(-34.6),
+            longitude: Some(-58.38),
+            ..Default::default()
+        };
+
+        assert_eq!(Wttr.request(&weather).unwrap().path, "/-34.6,-58.38");
+    }
+
+    #[test]
+    fn test_convert_with_open_meteo() {
+        let mut weather = Weather {
+            provider: Some(Service::OpenMeteo),
+            latitude: Some(-34.6),
+            longitude: Some(-58.38),
+            url: Some(serve(vec![(200, String::from(OPEN_METEO))])),
+            format: Some(String::from(
+                "{condition} {temp} {humidity} {wind} {sunrise}",
+            )),
+            ..Default::default()
+        };
+
+        let component = weather.convert().unwrap();
+
+        assert_eq!(component.icon, Sky::Rain.icon());
+        assert_eq!(component.value, "Rain 18°C 88% 12km/h SW 07:02");
+    }
+
+    #[test]
+    fn test_open_meteo_requires_coordinates() {
+        let weather = Weather {
+            provider: Some(Service::OpenMeteo),
+            location: String::from("Buenos+Aires"),
+            ..Default::default()
+        };
+
+        assert!(OpenMeteo.request(&weather).is_err());
+    }
+
+    #[test]
+    fn test_convert_with_openweathermap() {
+        let variable = "RSTATUSBAR_TEST_OPENWEATHERMAP_API_KEY";
+        unsafe { std::env::set_var(variable, "secret") };
+
+        let mut weather = Weather {
+            provider: Some(Service::OpenWeatherMap),
+            location: String::from("Buenos+Aires"),
+            api_key_env: Some(String::from(variable)),
+            url: Some(serve(vec![(200, String::from(OPENWEATHERMAP))])),
+            format: Some(String::from(
+                "{condition} {temp} ({feels_like}) {wind} {sunrise}-{sunset}",
+            )),
+            ..Default::default()
+        };
+
+        let request = OpenWeatherMap.request(&weather).unwrap();
+        assert!(request.query.contains(&("q", String::from("Buenos Aires"))));
+        assert!(request.query.contains(&("appid", String::from("secret"))));
+
+        let component = weather.convert().unwrap();
+
+        assert_eq!(component.icon, Sky::Cloudy.icon());
+        assert_eq!(
+            component.value,
+            "Broken clouds 21°C (22°C) 16km/h NE 06:40-17:55"
+        );
+    }
+
+    #[test]
+    fn test_openweathermap_without_api_key() {
+        let weather = Weather {
+            provider: Some(Service::OpenWeatherMap),
+            api_key_env: Some(String::from("RSTATUSBAR_TEST_UNSET_API_KEY")),
+            ..Default::default()
+        };
+
+        assert!(OpenWeatherMap.request(&weather).is_err());
+    }
+
+    #[test]
+    fn test_compass() {
+        assert_eq!(compass(0.0), "N");
+        assert_eq!(compass(45.0), "NE");
+        assert_eq!(compass(350.0), "N");
+        assert_eq!(compass(225.0), "SW");
+    }
+
+    #[test]
+    fn test_sky_from_other_providers() {
+        assert_eq!(Sky::from_wmo(0), Sky::Clear);
+        assert_eq!(Sky::from_wmo(63), Sky::Rain);
+        assert_eq!(Sky::from_wmo(75), Sky::Snow);
+        assert_eq!(Sky::from_wmo(95), Sky::Thunder);
+        assert_eq!(Sky::from_owm(211), Sky::Thunder);
+        assert_eq!(Sky::from_owm(701), Sky::Fog);
+        assert_eq!(Sky::from_owm(800), Sky::Clear);
+        assert_eq!(Sky::from_owm(803), Sky::Cloudy);
+    }
+
+    #[test]
+    fn test_sky_from_wwo() {
+        assert_eq!(Sky::from_wwo(113), Sky::Clear);
+        assert_eq!(Sky::from_wwo(122), Sky::Cloudy);
+        assert_eq!(Sky::from_wwo(248), Sky::Fog);
+        assert_eq!(Sky::from_wwo(296), Sky::Rain);
+        assert_eq!(Sky::from_wwo(308), Sky::HeavyRain);
+        assert_eq!(Sky::from_wwo(338), Sky::Snow);
+        assert_eq!(Sky::from_wwo(389), Sky::Thunder);
     }
 }
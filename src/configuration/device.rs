@@ -1,8 +1,9 @@
 use crate::component::section::Component;
 
 use super::components::{
-    battery::Battery, cpu::Cpu, date::Date, disk::Disk, memory::Memory, network::Network,
-    script::Script, temperature::Temperature, volume::Volume, weather::Weather,
+    battery::Battery, cpu::Cpu, date::Date, disk::Disk, diskio::DiskIo, fan::Fan, memory::Memory,
+    microphone::Microphone, network::Network, script::Script, swap::Swap, temperature::Temperature,
+    volume::Volume, weather::Weather,
 };
 
 pub enum Device {
@@ -10,39 +11,61 @@ pub enum Device {
     Cpu(Cpu),
     Date(Date),
     Disk(Disk),
+    DiskIo(DiskIo),
+    Fan(Fan),
     Memory(Memory),
+    Microphone(Microphone),
     Network(Network),
     Script(Script),
+    Swap(Swap),
     Temperature(Temperature),
     Volume(Volume),
     Weather(Weather),
 }
 
 impl Device {
-    pub fn get(&self) -> anyhow::Result<Component<'_>> {
+    pub fn get(&mut self) -> anyhow::Result<Component<'_>> {
         match self {
             Device::Battery(d) => d.convert(),
             Device::Cpu(d) => d.convert(),
             Device::Date(d) => d.convert(),
             Device::Disk(d) => d.convert(),
+            Device::DiskIo(d) => d.convert(),
+            Device::Fan(d) => d.convert(),
             Device::Memory(d) => d.convert(),
+            Device::Microphone(d) => d.convert(),
             Device::Network(d) => d.convert(),
             Device::Script(d) => d.convert(),
+            Device::Swap(d) => d.convert(),
             Device::Temperature(d) => d.convert(),
             Device::Volume(d) => d.convert(),
             Device::Weather(d) => d.convert(),
         }
     }
 
+    pub fn watcher(&mut self) -> Option<&mut dyn Watcher> {
+        match self {
+            Device::Date(d) => Some(d),
+            Device::Volume(d) => Some(d),
+            Device::Script(d) if d.is_stream() => Some(d),
+            Device::Weather(d) => Some(d),
+            _ => None,
+        }
+    }
+
     pub fn time(&self) -> u64 {
         match self {
             Device::Battery(d) => d.time(),
             Device::Cpu(d) => d.time(),
             Device::Date(d) => d.time(),
             Device::Disk(d) => d.time(),
+            Device::DiskIo(d) => d.time(),
+            Device::Fan(d) => d.time(),
             Device::Memory(d) => d.time(),
+            Device::Microphone(d) => d.time(),
             Device::Network(d) => d.time(),
             Device::Script(d) => d.time(),
+            Device::Swap(d) => d.time(),
             Device::Temperature(d) => d.time(),
             Device::Volume(d) => d.time(),
             Device::Weather(d) => d.time(),
@@ -51,6 +74,17 @@ impl Device {
 }
 
 pub trait Converter: Send {
-    fn convert(&self) -> anyhow::Result<Component<'_>>;
+    fn convert(&mut self) -> anyhow::Result<Component<'_>>;
     fn time(&self) -> u64;
+
+    /// Checks the configuration of the device once, when it is loaded
+    fn validate(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Devices that keep their source open and push a new component only when it
+/// changes, instead of being polled every `time` milliseconds
+pub trait Watcher: Send {
+    fn watch(&mut self, notify: &mut dyn FnMut(Component<'_>)) -> anyhow::Result<()>;
 }
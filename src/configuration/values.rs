@@ -4,9 +4,13 @@ use toml::from_str;
 
 use super::components::battery::Battery;
 use super::components::date::Date;
+use super::components::diskio::DiskIo;
+use super::components::fan::Fan;
 use super::components::general::General;
+use super::components::microphone::Microphone;
 use super::components::network::Network;
 use super::components::script::Script;
+use super::components::swap::Swap;
 use super::components::volume::Volume;
 use super::components::weather::Weather;
 use super::components::{cpu::Cpu, disk::Disk, memory::Memory, temperature::Temperature};
@@ -17,9 +21,13 @@ struct Config {
     pub general: Option<General>,
     pub cpu: Option<Cpu>,
     pub memory: Option<Memory>,
+    pub swap: Option<Swap>,
     pub disk: Option<Disk>,
+    pub diskio: Option<DiskIo>,
     pub temperature: Option<Temperature>,
+    pub fan: Option<Fan>,
     pub volume: Option<Volume>,
+    pub microphone: Option<Microphone>,
     pub network: Option<Network>,
     pub battery: Option<Battery>,
     pub script: Option<Script>,
@@ -27,8 +35,9 @@ struct Config {
     pub date: Option<Date>,
 }
 
-pub fn get_configuration() -> (General, Vec<Device>) {
-    let home_path = std::env::var_os("HOME").expect("No HOME variable set.");
+pub fn get_configuration() -> anyhow::Result<(General, Vec<Device>)> {
+    let home_path =
+        std::env::var_os("HOME").ok_or_else(|| anyhow::anyhow!("No HOME variable set."))?;
 
     let config_path = format!(
         "{}{}",
@@ -36,24 +45,10 @@ pub fn get_configuration() -> (General, Vec<Device>) {
         "/.config/rstatusbar/config.toml"
     );
 
-    if let Ok(toml) = fs::read_to_string(config_path) {
-        let config: Config = from_str(&toml).expect("Error parsing TOML");
-        let mut values: Vec<Device> = Vec::new();
-
-        add_device(config.cpu, &mut values, Device::Cpu);
-        add_device(config.memory, &mut values, Device::Memory);
-        add_device(config.temperature, &mut values, Device::Temperature);
-        add_device(config.disk, &mut values, Device::Disk);
-        add_device(config.volume, &mut values, Device::Volume);
-        add_device(config.network, &mut values, Device::Network);
-        add_device(config.battery, &mut values, Device::Battery);
-        add_device(config.script, &mut values, Device::Script);
-        add_device(config.weather, &mut values, Device::Weather);
-        add_device(config.date, &mut values, Device::Date);
-
-        (config.general.unwrap_or_default(), values)
+    if let Ok(toml) = fs::read_to_string(&config_path) {
+        parse_configuration(&toml).map_err(|e| anyhow::anyhow!("{config_path}: {e}"))
     } else {
-        (
+        Ok((
             General::default(),
             vec![
                 Device::Cpu(Cpu::default()),
@@ -64,18 +59,42 @@ pub fn get_configuration() -> (General, Vec<Device>) {
                 Device::Network(Network::default()),
                 Device::Date(Date::default()),
             ],
-        )
+        ))
     }
 }
 
-fn add_device<T, F>(h: Option<T>, v: &mut Vec<Device>, f: F)
+fn parse_configuration(toml: &str) -> anyhow::Result<(General, Vec<Device>)> {
+    let config: Config = from_str(toml).map_err(|e| anyhow::anyhow!("Error parsing TOML: {e}"))?;
+    let mut values: Vec<Device> = Vec::new();
+
+    add_device(config.cpu, &mut values, Device::Cpu)?;
+    add_device(config.memory, &mut values, Device::Memory)?;
+    add_device(config.swap, &mut values, Device::Swap)?;
+    add_device(config.temperature, &mut values, Device::Temperature)?;
+    add_device(config.fan, &mut values, Device::Fan)?;
+    add_device(config.disk, &mut values, Device::Disk)?;
+    add_device(config.diskio, &mut values, Device::DiskIo)?;
+    add_device(config.volume, &mut values, Device::Volume)?;
+    add_device(config.microphone, &mut values, Device::Microphone)?;
+    add_device(config.network, &mut values, Device::Network)?;
+    add_device(config.battery, &mut values, Device::Battery)?;
+    add_device(config.script, &mut values, Device::Script)?;
+    add_device(config.weather, &mut values, Device::Weather)?;
+    add_device(config.date, &mut values, Device::Date)?;
+
+    Ok((config.general.unwrap_or_default(), values))
+}
+
+fn add_device<T, F>(h: Option<T>, v: &mut Vec<Device>, f: F) -> anyhow::Result<()>
 where
     T: Converter,
     F: FnOnce(T) -> Device,
 {
     if let Some(value) = h {
+        value.validate()?;
         v.push(f(value));
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -147,7 +166,7 @@ mod tests {
         let mut file = File::create(&config_path).unwrap();
         writeln!(file, "{config_content}").unwrap();
 
-        let (general, converters) = get_configuration();
+        let (general, converters) = get_configuration().unwrap();
 
         assert!(general.separator.is_some());
 
@@ -159,8 +178,25 @@ mod tests {
         let dir = tempdir().unwrap();
         let home_path = dir.path().to_str().unwrap();
         unsafe { env::set_var("HOME", home_path) };
-        let (general, converters) = get_configuration();
+        let (general, converters) = get_configuration().unwrap();
         assert!(general.separator.is_some());
         assert_eq!(converters.len(), 7);
     }
+
+    #[test]
+    fn test_parse_configuration_with_invalid_date_format() {
+        let config_content = r#"
+            [date]
+            format = "%Y-%m-%d %Q"
+        "#;
+
+        let error = parse_configuration(config_content).err().unwrap();
+
+        assert!(error.to_string().contains("%Q"));
+    }
+
+    #[test]
+    fn test_parse_configuration_with_invalid_toml() {
+        assert!(parse_configuration("[cpu").is_err());
+    }
 }
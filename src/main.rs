@@ -3,5 +3,8 @@ mod configuration;
 mod statusbar;
 
 fn main() {
-    statusbar::execute();
+    if let Err(e) = statusbar::execute() {
+        eprintln!("rstatusbar configuration error: {e}");
+        std::process::exit(1);
+    }
 }
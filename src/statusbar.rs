@@ -5,23 +5,35 @@ use std::thread;
 use std::time::Duration;
 
 use crate::configuration::components::general::General;
+use crate::configuration::components::script::{kill_streams, kill_streams_on_exit};
 use crate::configuration::device::Device;
 use crate::configuration::values::get_configuration;
 
 const TIME: u64 = 100;
 
-pub fn execute() {
+pub fn execute() -> anyhow::Result<()> {
     let mut values: Vec<Arc<Mutex<String>>> = Vec::new();
-    let (general, devices) = get_configuration();
+    let (general, devices) = get_configuration()?;
+
+    if devices
+        .iter()
+        .any(|dev| matches!(dev, Device::Script(script) if script.is_stream()))
+    {
+        kill_streams_on_exit();
+    }
 
     let mut grouped_devices_by_time = HashMap::<u64, Vec<(Arc<Mutex<String>>, Device)>>::new();
 
-    for dev in devices {
+    for mut dev in devices {
         let cache = Arc::new(Mutex::new(String::new()));
-        grouped_devices_by_time
-            .entry(dev.time())
-            .or_default()
-            .push((cache.clone(), dev));
+        if dev.watcher().is_some() {
+            create_watched_component(dev, cache.clone());
+        } else {
+            grouped_devices_by_time
+                .entry(dev.time())
+                .or_default()
+                .push((cache.clone(), dev));
+        }
 
         values.push(cache);
     }
@@ -31,12 +43,14 @@ pub fn execute() {
     }
 
     create_statusbar(general, values);
+    kill_streams();
+    Ok(())
 }
 
-fn create_grouped_component(devices: Vec<(Arc<Mutex<String>>, Device)>, time: u64) {
+fn create_grouped_component(mut devices: Vec<(Arc<Mutex<String>>, Device)>, time: u64) {
     thread::spawn(move || {
         loop {
-            for (cache, device) in &devices {
+            for (cache, device) in &mut devices {
                 let component = device.get();
                 match component {
                     Ok(comp) => {
@@ -56,6 +70,28 @@ fn create_grouped_component(devices: Vec<(Arc<Mutex<String>>, Device)>, time: u6
     });
 }
 
+fn create_watched_component(mut device: Device, cache: Arc<Mutex<String>>) {
+    thread::spawn(move || {
+        let time = device.time();
+        let Some(watcher) = device.watcher() else {
+            return;
+        };
+        loop {
+            let result = watcher.watch(&mut |comp| {
+                if let Ok(mut lock) = cache.lock() {
+                    *lock = comp.to_string();
+                } else {
+                    eprintln!("Watched component lock error");
+                }
+            });
+            if let Err(e) = result {
+                eprintln!("Watched converter error: {e}");
+            }
+            thread::sleep(Duration::from_millis(time));
+        }
+    });
+}
+
 fn create_statusbar(general: General, list: Vec<Arc<Mutex<String>>>) {
     let separator = &general.separator.clone().unwrap();
     loop {